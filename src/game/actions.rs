@@ -0,0 +1,528 @@
+//! What the player can do, bound to keys, mouse buttons and gamepad buttons.
+//! Gameplay reads the [`ActionState`] instead of the raw input, so the player
+//! can change the bindings in the [`ActionMap`], which is part of the save data.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::ui::controls::{text_input_focused, TextInput};
+
+/// How many bindings an action can have. Binding another one replaces the oldest.
+pub const MAX_BINDINGS: usize = 3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ActionMap>();
+    app.init_resource::<ActionState>();
+    app.add_systems(PreUpdate, update_action_state.after(InputSystem));
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Serialize, Deserialize,
+)]
+pub enum InputAction {
+    Jump,
+    SpeedUp,
+    SpeedDown,
+    Pause,
+    Restart,
+    /// Leave the level or the editor.
+    MenuBack,
+    ToggleGhost,
+    SaveReplay,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Move the focus to another widget.
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    /// Press the focused widget.
+    MenuConfirm,
+    EditMotor,
+    EditColor,
+    EditTeeth,
+    EditStart,
+    EditGoal,
+    RemoveGear,
+    /// Undo, redo and save the level while holding Ctrl.
+    Undo,
+    Redo,
+    SaveLevel,
+    PlayTest,
+}
+
+/// Where an action is read. Actions that are never read in the same place can
+/// share a binding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionContext {
+    Menus,
+    Playing,
+    Editor,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 27] = [
+        InputAction::Jump,
+        InputAction::SpeedUp,
+        InputAction::SpeedDown,
+        InputAction::Pause,
+        InputAction::Restart,
+        InputAction::MenuBack,
+        InputAction::ToggleGhost,
+        InputAction::SaveReplay,
+        InputAction::MoveUp,
+        InputAction::MoveDown,
+        InputAction::MoveLeft,
+        InputAction::MoveRight,
+        InputAction::MenuUp,
+        InputAction::MenuDown,
+        InputAction::MenuLeft,
+        InputAction::MenuRight,
+        InputAction::MenuConfirm,
+        InputAction::EditMotor,
+        InputAction::EditColor,
+        InputAction::EditTeeth,
+        InputAction::EditStart,
+        InputAction::EditGoal,
+        InputAction::RemoveGear,
+        InputAction::Undo,
+        InputAction::Redo,
+        InputAction::SaveLevel,
+        InputAction::PlayTest,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InputAction::Jump => "Jump",
+            InputAction::SpeedUp => "Speed up",
+            InputAction::SpeedDown => "Slow down",
+            InputAction::Pause => "Pause",
+            InputAction::Restart => "Restart",
+            InputAction::MenuBack => "Back",
+            InputAction::ToggleGhost => "Show ghost",
+            InputAction::SaveReplay => "Save replay",
+            InputAction::MoveUp => "Move up",
+            InputAction::MoveDown => "Move down",
+            InputAction::MoveLeft => "Move left",
+            InputAction::MoveRight => "Move right",
+            InputAction::MenuUp => "Menu up",
+            InputAction::MenuDown => "Menu down",
+            InputAction::MenuLeft => "Menu left",
+            InputAction::MenuRight => "Menu right",
+            InputAction::MenuConfirm => "Confirm",
+            InputAction::EditMotor => "Motor",
+            InputAction::EditColor => "Color",
+            InputAction::EditTeeth => "Shift teeth",
+            InputAction::EditStart => "Player start",
+            InputAction::EditGoal => "Goal",
+            InputAction::RemoveGear => "Remove gear",
+            InputAction::Undo => "Undo",
+            InputAction::Redo => "Redo",
+            InputAction::SaveLevel => "Save level",
+            InputAction::PlayTest => "Play-test",
+        }
+    }
+
+    pub fn contexts(self) -> &'static [ActionContext] {
+        match self {
+            InputAction::MenuUp
+            | InputAction::MenuDown
+            | InputAction::MenuLeft
+            | InputAction::MenuRight
+            | InputAction::MenuConfirm => &[ActionContext::Menus],
+            InputAction::MenuBack => &[ActionContext::Playing, ActionContext::Editor],
+            InputAction::EditMotor
+            | InputAction::EditColor
+            | InputAction::EditTeeth
+            | InputAction::EditStart
+            | InputAction::EditGoal
+            | InputAction::RemoveGear
+            | InputAction::Undo
+            | InputAction::Redo
+            | InputAction::SaveLevel
+            | InputAction::PlayTest => &[ActionContext::Editor],
+            _ => &[ActionContext::Playing],
+        }
+    }
+
+    /// Whether this action and `other` are ever read in the same place.
+    fn shares_context(self, other: InputAction) -> bool {
+        self.contexts()
+            .iter()
+            .any(|context| other.contexts().contains(context))
+    }
+
+    fn default_bindings(self) -> Vec<Binding> {
+        use Binding::*;
+        use GamepadButtonType as Pad;
+        match self {
+            InputAction::Jump => vec![Key(KeyCode::Space), Gamepad(Pad::South)],
+            InputAction::SpeedUp => vec![Key(KeyCode::KeyE), Gamepad(Pad::RightTrigger)],
+            InputAction::SpeedDown => vec![Key(KeyCode::KeyQ), Gamepad(Pad::LeftTrigger)],
+            InputAction::Pause => vec![Key(KeyCode::KeyP), Gamepad(Pad::Start)],
+            InputAction::Restart => vec![Key(KeyCode::KeyR), Gamepad(Pad::Select)],
+            InputAction::MenuBack => vec![Key(KeyCode::Escape), Gamepad(Pad::East)],
+            InputAction::ToggleGhost => vec![Key(KeyCode::KeyG), Gamepad(Pad::North)],
+            InputAction::SaveReplay => vec![Key(KeyCode::F5)],
+            InputAction::MoveUp => vec![Key(KeyCode::KeyW), Key(KeyCode::ArrowUp)],
+            InputAction::MoveDown => vec![Key(KeyCode::KeyS), Key(KeyCode::ArrowDown)],
+            InputAction::MoveLeft => vec![Key(KeyCode::KeyA), Key(KeyCode::ArrowLeft)],
+            InputAction::MoveRight => vec![Key(KeyCode::KeyD), Key(KeyCode::ArrowRight)],
+            InputAction::MenuUp => vec![Key(KeyCode::ArrowUp), Gamepad(Pad::DPadUp)],
+            InputAction::MenuDown => vec![Key(KeyCode::ArrowDown), Gamepad(Pad::DPadDown)],
+            InputAction::MenuLeft => vec![Key(KeyCode::ArrowLeft), Gamepad(Pad::DPadLeft)],
+            InputAction::MenuRight => vec![Key(KeyCode::ArrowRight), Gamepad(Pad::DPadRight)],
+            InputAction::MenuConfirm => vec![Key(KeyCode::Enter), Gamepad(Pad::South)],
+            InputAction::EditMotor => vec![Key(KeyCode::KeyR)],
+            InputAction::EditColor => vec![Key(KeyCode::KeyC)],
+            InputAction::EditTeeth => vec![Key(KeyCode::KeyT)],
+            InputAction::EditStart => vec![Key(KeyCode::KeyP)],
+            InputAction::EditGoal => vec![Key(KeyCode::KeyG)],
+            InputAction::RemoveGear => vec![Key(KeyCode::Delete), Key(KeyCode::Backspace)],
+            InputAction::Undo => vec![Key(KeyCode::KeyZ)],
+            InputAction::Redo => vec![Key(KeyCode::KeyY)],
+            InputAction::SaveLevel => vec![Key(KeyCode::KeyS)],
+            InputAction::PlayTest => vec![Key(KeyCode::Enter)],
+        }
+    }
+}
+
+/// A button that can trigger an action. Gamepad buttons work on any gamepad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                write!(f, "{name}")
+            }
+            Binding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            Binding::Gamepad(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+/// Binding a button that's already bound to another action read in the same
+/// place would make one button do two things.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{binding} is already bound to {}", action.name())]
+pub struct BindingConflict {
+    pub binding: Binding,
+    pub action: InputAction,
+}
+
+/// The bindings of every action. Actions missing from saved bindings get
+/// their default ones, so actions can be added without a new save data version.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<InputAction, Vec<Binding>>",
+    into = "BTreeMap<InputAction, Vec<Binding>>"
+)]
+pub struct ActionMap {
+    bindings: BTreeMap<InputAction, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        BTreeMap::new().into()
+    }
+}
+
+impl From<BTreeMap<InputAction, Vec<Binding>>> for ActionMap {
+    fn from(mut bindings: BTreeMap<InputAction, Vec<Binding>>) -> Self {
+        for action in InputAction::ALL {
+            bindings
+                .entry(action)
+                .or_insert_with(|| action.default_bindings());
+        }
+        Self { bindings }
+    }
+}
+
+impl From<ActionMap> for BTreeMap<InputAction, Vec<Binding>> {
+    fn from(map: ActionMap) -> Self {
+        map.bindings
+    }
+}
+
+impl ActionMap {
+    pub fn bindings(&self, action: InputAction) -> &[Binding] {
+        &self.bindings[&action]
+    }
+
+    /// The bindings of `action`, to show to the player.
+    pub fn describe(&self, action: InputAction) -> String {
+        let names: Vec<_> = self
+            .bindings(action)
+            .iter()
+            .map(|binding| binding.to_string())
+            .collect();
+        if names.is_empty() {
+            "Unbound".to_string()
+        } else {
+            names.join(" / ")
+        }
+    }
+
+    /// The action `binding` is bound to where `action` is read, if any.
+    pub fn action(&self, action: InputAction, binding: Binding) -> Option<InputAction> {
+        InputAction::ALL
+            .into_iter()
+            .find(|other| other.shares_context(action) && self.bindings(*other).contains(&binding))
+    }
+
+    /// Add `binding` to `action`, unless another action read in the same
+    /// place has it.
+    pub fn bind(&mut self, action: InputAction, binding: Binding) -> Result<(), BindingConflict> {
+        match self.action(action, binding) {
+            Some(bound) if bound == action => Ok(()),
+            Some(bound) => Err(BindingConflict {
+                binding,
+                action: bound,
+            }),
+            None => {
+                let bindings = self.bindings.get_mut(&action).unwrap();
+                if bindings.len() >= MAX_BINDINGS {
+                    bindings.remove(0);
+                }
+                bindings.push(binding);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn unbind_all(&mut self, action: InputAction) {
+        self.bindings.get_mut(&action).unwrap().clear();
+    }
+
+    /// The actions with a binding that an earlier action read in the same
+    /// place also has. Bindings can only end up like this when they're edited
+    /// by hand.
+    pub fn conflicts(&self) -> Vec<(InputAction, BindingConflict)> {
+        InputAction::ALL
+            .into_iter()
+            .flat_map(|action| {
+                self.bindings(action).iter().filter_map(move |binding| {
+                    let bound = self.action(action, *binding)?;
+                    (bound != action).then_some((
+                        action,
+                        BindingConflict {
+                            binding: *binding,
+                            action: bound,
+                        },
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+/// The actions the player is taking this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Take a press of `action` this frame, so nothing else sees it as just
+    /// pressed. Returns whether it was just pressed.
+    pub fn consume(&mut self, action: InputAction) -> bool {
+        self.just_pressed.remove(&action)
+    }
+}
+
+/// Run condition for systems that respond to the player starting `action`.
+pub fn action_just_pressed(action: InputAction) -> impl Fn(Res<ActionState>) -> bool {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
+
+/// The buttons of every kind of input device.
+#[derive(SystemParam)]
+pub struct Buttons<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_buttons: Res<'w, ButtonInput<GamepadButton>>,
+}
+
+impl Buttons<'_> {
+    pub fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse.pressed(button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .pressed(GamepadButton::new(gamepad, button))
+            }),
+        }
+    }
+
+    pub fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.just_pressed(key),
+            Binding::Mouse(button) => self.mouse.just_pressed(button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| {
+                self.gamepad_buttons
+                    .just_pressed(GamepadButton::new(gamepad, button))
+            }),
+        }
+    }
+
+    /// A button pressed this frame, if any.
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        let key = self
+            .keys
+            .get_just_pressed()
+            .next()
+            .copied()
+            .map(Binding::Key);
+        let mouse = || {
+            self.mouse
+                .get_just_pressed()
+                .next()
+                .copied()
+                .map(Binding::Mouse)
+        };
+        let gamepad = || {
+            self.gamepad_buttons
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Gamepad(button.button_type))
+        };
+        key.or_else(mouse).or_else(gamepad)
+    }
+}
+
+pub fn update_action_state(
+    map: Res<ActionMap>,
+    buttons: Buttons,
+    input_query: Query<&TextInput>,
+    mut state: ResMut<ActionState>,
+) {
+    state.pressed.clear();
+    state.just_pressed.clear();
+    // Keys typed into a text input aren't meant as actions.
+    if text_input_focused(input_query) {
+        return;
+    }
+    for action in InputAction::ALL {
+        let bindings = map.bindings(action);
+        if bindings.iter().any(|binding| buttons.pressed(*binding)) {
+            state.pressed.insert(action);
+        }
+        if bindings
+            .iter()
+            .any(|binding| buttons.just_pressed(*binding))
+        {
+            state.just_pressed.insert(action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_conflict_with_other_actions() {
+        let mut map = ActionMap::default();
+        let space = Binding::Key(KeyCode::Space);
+        assert_eq!(map.bind(InputAction::Jump, space), Ok(()));
+        assert_eq!(
+            map.bind(InputAction::Pause, space),
+            Err(BindingConflict {
+                binding: space,
+                action: InputAction::Jump
+            })
+        );
+        map.unbind_all(InputAction::Jump);
+        assert_eq!(map.bind(InputAction::Pause, space), Ok(()));
+        assert_eq!(
+            map.action(InputAction::Jump, space),
+            Some(InputAction::Pause)
+        );
+        assert!(map.conflicts().is_empty());
+
+        for button in [MouseButton::Left, MouseButton::Right, MouseButton::Middle] {
+            map.bind(InputAction::Jump, Binding::Mouse(button)).unwrap();
+        }
+        map.bind(InputAction::Jump, space).unwrap_err();
+        map.bind(InputAction::Jump, Binding::Key(KeyCode::KeyJ))
+            .unwrap();
+        assert_eq!(map.bindings(InputAction::Jump).len(), MAX_BINDINGS);
+        assert_eq!(
+            map.action(InputAction::Jump, Binding::Mouse(MouseButton::Left)),
+            None
+        );
+    }
+
+    #[test]
+    fn bindings_are_shared_between_contexts() {
+        let mut map = ActionMap::default();
+        assert!(map.conflicts().is_empty());
+        let enter = Binding::Key(KeyCode::Enter);
+        assert_eq!(
+            map.action(InputAction::Jump, enter),
+            None,
+            "menus and play-testing don't conflict with playing"
+        );
+        assert_eq!(map.bind(InputAction::Jump, enter), Ok(()));
+        assert_eq!(
+            map.bind(InputAction::Undo, Binding::Key(KeyCode::Escape)),
+            Err(BindingConflict {
+                binding: Binding::Key(KeyCode::Escape),
+                action: InputAction::MenuBack
+            })
+        );
+    }
+
+    #[test]
+    fn saved_bindings_keep_missing_actions_and_conflicts() {
+        let map: ActionMap =
+            ron::from_str("{Jump: [Key(KeyP)], Restart: [], SpeedUp: [Mouse(Left)]}").unwrap();
+        assert_eq!(map.bindings(InputAction::Restart), []);
+        assert_eq!(
+            map.bindings(InputAction::SpeedDown),
+            InputAction::SpeedDown.default_bindings()
+        );
+        assert_eq!(
+            map.conflicts(),
+            [(
+                InputAction::Pause,
+                BindingConflict {
+                    binding: Binding::Key(KeyCode::KeyP),
+                    action: InputAction::Jump
+                }
+            )]
+        );
+        let source = ron::to_string(&map).unwrap();
+        assert_eq!(ron::from_str::<ActionMap>(&source).unwrap(), map);
+    }
+}
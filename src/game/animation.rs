@@ -8,7 +8,7 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 
-use super::{audio::sfx::PlaySfx, movement::MovementController};
+use super::{audio::sfx::PlaySfx, movement::MovementController, settings::reduced_motion};
 use crate::AppSet;
 
 pub(super) fn plugin(app: &mut App) {
@@ -17,7 +17,9 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
-            update_animation_timer.in_set(AppSet::TickTimers),
+            update_animation_timer
+                .in_set(AppSet::TickTimers)
+                .run_if(not(reduced_motion)),
             (
                 update_animation_movement,
                 update_animation_atlas,
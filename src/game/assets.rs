@@ -1,9 +1,15 @@
 use bevy::{
+    asset::{LoadState, RecursiveDependencyLoadState},
     prelude::*,
     render::texture::{ImageLoaderSettings, ImageSampler},
     utils::HashMap,
 };
 
+use super::{
+    campaign::{Campaign, CampaignLoader},
+    level::{Level, LevelLoader},
+};
+
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<ImageKey>>();
     app.init_resource::<HandleMap<ImageKey>>();
@@ -13,6 +19,16 @@ pub(super) fn plugin(app: &mut App) {
 
     app.register_type::<HandleMap<SoundtrackKey>>();
     app.init_resource::<HandleMap<SoundtrackKey>>();
+
+    app.init_asset::<Level>();
+    app.init_asset_loader::<LevelLoader>();
+    app.register_type::<HandleMap<LevelKey>>();
+    app.init_resource::<HandleMap<LevelKey>>();
+
+    app.init_asset::<Campaign>();
+    app.init_asset_loader::<CampaignLoader>();
+    app.register_type::<HandleMap<CampaignKey>>();
+    app.init_resource::<HandleMap<CampaignKey>>();
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
@@ -124,6 +140,46 @@ impl FromWorld for HandleMap<SoundtrackKey> {
     }
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+pub enum LevelKey {
+    ThreeGears,
+}
+
+impl AssetKey for LevelKey {
+    type Asset = Level;
+}
+
+impl FromWorld for HandleMap<LevelKey> {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        [(
+            LevelKey::ThreeGears,
+            asset_server.load("levels/three_gears.level.ron"),
+        )]
+        .into()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+pub enum CampaignKey {
+    Main,
+}
+
+impl AssetKey for CampaignKey {
+    type Asset = Campaign;
+}
+
+impl FromWorld for HandleMap<CampaignKey> {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        [(
+            CampaignKey::Main,
+            asset_server.load("levels/main.campaign.ron"),
+        )]
+        .into()
+    }
+}
+
 pub trait AssetKey: Sized {
     type Asset: Asset;
 }
@@ -146,4 +202,18 @@ impl<K: AssetKey> HandleMap<K> {
         self.values()
             .all(|x| asset_server.is_loaded_with_dependencies(x))
     }
+
+    /// Like [`HandleMap::all_loaded`], but also counts assets that failed to load,
+    /// or whose dependencies did, as finished. The asset server logs the reason
+    /// for the failure.
+    pub fn all_finished(&self, asset_server: &AssetServer) -> bool {
+        self.values().all(|x| {
+            asset_server.is_loaded_with_dependencies(x)
+                || matches!(asset_server.load_state(x), LoadState::Failed(_))
+                || matches!(
+                    asset_server.recursive_dependency_load_state(x),
+                    RecursiveDependencyLoadState::Failed
+                )
+        })
+    }
 }
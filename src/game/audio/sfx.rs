@@ -1,25 +1,39 @@
 use bevy::{audio::PlaybackMode, prelude::*};
 use rand::seq::SliceRandom;
 
-use crate::game::assets::{HandleMap, SfxKey};
+use crate::game::{
+    assets::{HandleMap, SfxKey},
+    gear_jump::{PlayerJumped, PlayerLanded},
+    mechanics::gear::{RotationalMovement, BASE_GEAR_TEETH},
+    rotational_movement::SpeedChanged,
+    save::SaveData,
+    spawn::gear::Gear,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(play_sfx);
+    app.observe(play_jump_sfx);
+    app.observe(play_landing_sfx);
+    app.observe(play_speed_sfx);
 }
 
 fn play_sfx(
     trigger: Trigger<PlaySfx>,
     mut commands: Commands,
     sfx_handles: Res<HandleMap<SfxKey>>,
+    save: Res<SaveData>,
 ) {
-    let sfx_key = match trigger.event() {
-        PlaySfx::Key(key) => *key,
-        PlaySfx::RandomStep => random_step(),
+    let (sfx_key, speed) = match trigger.event() {
+        PlaySfx::Key(key) => (*key, 1.0),
+        PlaySfx::RandomStep => (random_step(), 1.0),
+        PlaySfx::Pitched(key, speed) => (*key, *speed),
     };
     commands.spawn(AudioSourceBundle {
         source: sfx_handles[&sfx_key].clone_weak(),
         settings: PlaybackSettings {
             mode: PlaybackMode::Despawn,
+            volume: save.settings.sfx(),
+            speed,
             ..default()
         },
     });
@@ -30,6 +44,39 @@ fn play_sfx(
 pub enum PlaySfx {
     Key(SfxKey),
     RandomStep,
+    /// Played faster for a higher pitch, or slower for a lower one.
+    Pitched(SfxKey, f32),
+}
+
+/// Smaller gears sound higher, and bigger ones lower.
+fn gear_pitch(gears: &Query<&RotationalMovement, With<Gear>>, gear: Entity) -> f32 {
+    gears.get(gear).map_or(1.0, |movement| {
+        (BASE_GEAR_TEETH as f32 / movement.teeth() as f32).sqrt()
+    })
+}
+
+fn play_jump_sfx(
+    trigger: Trigger<PlayerJumped>,
+    mut commands: Commands,
+    gears: Query<&RotationalMovement, With<Gear>>,
+) {
+    let pitch = gear_pitch(&gears, trigger.event().from);
+    commands.trigger(PlaySfx::Pitched(SfxKey::ButtonHover, pitch));
+}
+
+fn play_landing_sfx(
+    trigger: Trigger<PlayerLanded>,
+    mut commands: Commands,
+    gears: Query<&RotationalMovement, With<Gear>>,
+) {
+    let pitch = gear_pitch(&gears, trigger.event().to);
+    commands.trigger(PlaySfx::Pitched(random_step(), pitch));
+}
+
+/// Speeding up clicks higher, and slowing down lower.
+fn play_speed_sfx(trigger: Trigger<SpeedChanged>, mut commands: Commands) {
+    let SpeedChanged { from, to } = *trigger.event();
+    commands.trigger(PlaySfx::Pitched(SfxKey::ButtonPress, (to / from).sqrt()));
 }
 
 fn random_step() -> SfxKey {
@@ -1,10 +1,17 @@
 use bevy::{audio::PlaybackMode, prelude::*};
 
-use crate::game::assets::{HandleMap, SoundtrackKey};
+use crate::game::{
+    assets::{HandleMap, SoundtrackKey},
+    save::SaveData,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<IsSoundtrack>();
     app.observe(play_soundtrack);
+    app.add_systems(
+        Update,
+        apply_music_volume.run_if(resource_changed::<SaveData>),
+    );
 }
 
 fn play_soundtrack(
@@ -12,6 +19,7 @@ fn play_soundtrack(
     mut commands: Commands,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
     soundtrack_query: Query<Entity, With<IsSoundtrack>>,
+    save: Res<SaveData>,
 ) {
     for entity in &soundtrack_query {
         commands.entity(entity).despawn_recursive();
@@ -26,6 +34,7 @@ fn play_soundtrack(
             source: soundtrack_handles[&soundtrack_key].clone_weak(),
             settings: PlaybackSettings {
                 mode: PlaybackMode::Loop,
+                volume: save.settings.music(),
                 ..default()
             },
         },
@@ -33,6 +42,13 @@ fn play_soundtrack(
     ));
 }
 
+/// Change the volume of the soundtrack that's playing when the setting changes.
+fn apply_music_volume(save: Res<SaveData>, sink_query: Query<&AudioSink, With<IsSoundtrack>>) {
+    for sink in &sink_query {
+        sink.set_volume(save.settings.music_volume);
+    }
+}
+
 /// Trigger this event to play or disable the soundtrack.
 /// Playing a new soundtrack will overwrite the previous one.
 /// Soundtracks will loop.
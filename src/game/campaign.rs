@@ -0,0 +1,251 @@
+//! The campaign: the game's levels in the order they are played, grouped into
+//! worlds. Completing a level unlocks the next one. The campaign is described
+//! by `assets/levels/main.campaign.ron`, next to the level files it lists.
+
+use std::collections::BTreeSet;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::game::{
+    assets::{CampaignKey, HandleMap},
+    gear_jump::LevelCompleted,
+    level::Level,
+    save::SaveData,
+    spawn::level::CurrentLevel,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(complete_campaign_level);
+}
+
+#[derive(Asset, TypePath, Debug)]
+pub struct Campaign {
+    pub worlds: Vec<CampaignWorld>,
+}
+
+#[derive(Debug)]
+pub struct CampaignWorld {
+    pub name: String,
+    pub levels: Vec<CampaignLevel>,
+}
+
+#[derive(Debug)]
+pub struct CampaignLevel {
+    /// The name of the level file, which identifies the level in the player's progress.
+    pub id: String,
+    pub handle: Handle<Level>,
+}
+
+impl Campaign {
+    /// The level to play next: the first one that hasn't been completed, which
+    /// is the one completing the previous level unlocked. Once the whole
+    /// campaign is completed, it starts over from the first level.
+    pub fn next_level(
+        &self,
+        progress: &CampaignProgress,
+    ) -> Option<(&CampaignWorld, &CampaignLevel)> {
+        let mut levels = self
+            .worlds
+            .iter()
+            .flat_map(|world| world.levels.iter().map(move |level| (world, level)));
+        levels
+            .clone()
+            .find(|(_, level)| !progress.is_completed(&level.id))
+            .or_else(|| levels.next())
+    }
+
+    fn level_with_handle(&self, handle: &Handle<Level>) -> Option<&CampaignLevel> {
+        self.worlds
+            .iter()
+            .flat_map(|world| &world.levels)
+            .find(|level| level.handle.id() == handle.id())
+    }
+}
+
+/// Finds out which campaign level a level is. The campaign id is what the
+/// player's progress, records and best runs are kept by.
+#[derive(SystemParam)]
+pub struct CampaignIds<'w> {
+    campaign_handles: Res<'w, HandleMap<CampaignKey>>,
+    campaigns: Res<'w, Assets<Campaign>>,
+}
+
+impl CampaignIds<'_> {
+    /// The campaign id of `level`, unless it isn't one of the campaign's
+    /// levels, like generated levels and levels from the editor.
+    pub fn get(&self, level: &Handle<Level>) -> Option<&str> {
+        let campaign = self
+            .campaigns
+            .get(&self.campaign_handles[&CampaignKey::Main])?;
+        Some(&campaign.level_with_handle(level)?.id)
+    }
+}
+
+/// The campaign levels the player has completed. It's part of the [`SaveData`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    completed: BTreeSet<String>,
+}
+
+impl CampaignProgress {
+    pub fn is_completed(&self, id: &str) -> bool {
+        self.completed.contains(id)
+    }
+
+    /// Mark the level as completed. Returns whether it wasn't already.
+    pub fn complete(&mut self, id: &str) -> bool {
+        self.completed.insert(id.to_string())
+    }
+}
+
+fn complete_campaign_level(
+    _trigger: Trigger<LevelCompleted>,
+    current_level: Option<Res<CurrentLevel>>,
+    campaign_ids: CampaignIds,
+    mut save: ResMut<SaveData>,
+) {
+    let Some(id) = current_level.and_then(|current| campaign_ids.get(&current.0)) else {
+        return;
+    };
+    // Only touch the save data when there's something new, so it isn't written needlessly.
+    if !save.progress.is_completed(id) {
+        info!("Completed campaign level \"{id}\"");
+        save.progress.complete(id);
+    }
+}
+
+/// A campaign as described by a campaign file.
+#[derive(Deserialize)]
+struct CampaignDefinition {
+    worlds: Vec<WorldDefinition>,
+}
+
+#[derive(Deserialize)]
+struct WorldDefinition {
+    name: String,
+    /// Level file names without the `.level.ron` extension, in the same directory.
+    levels: Vec<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum CampaignLoaderError {
+    #[error("could not read campaign file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse campaign file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("the campaign has no levels")]
+    NoLevels,
+}
+
+#[derive(Default)]
+pub struct CampaignLoader;
+
+impl AssetLoader for CampaignLoader {
+    type Asset = Campaign;
+    type Settings = ();
+    type Error = CampaignLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Campaign, CampaignLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let definition = ron::de::from_bytes::<CampaignDefinition>(&bytes)?;
+        if definition
+            .worlds
+            .iter()
+            .all(|world| world.levels.is_empty())
+        {
+            return Err(CampaignLoaderError::NoLevels);
+        }
+
+        let directory = load_context
+            .path()
+            .parent()
+            .map(|directory| directory.to_path_buf())
+            .unwrap_or_default();
+        let worlds = definition
+            .worlds
+            .into_iter()
+            .map(|world| CampaignWorld {
+                name: world.name,
+                levels: world
+                    .levels
+                    .into_iter()
+                    .map(|id| CampaignLevel {
+                        handle: load_context.load(directory.join(format!("{id}.level.ron"))),
+                        id,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Ok(Campaign { worlds })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["campaign.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::solver;
+
+    fn campaign() -> Campaign {
+        let world = |name: &str, ids: &[&str]| CampaignWorld {
+            name: name.to_string(),
+            levels: ids
+                .iter()
+                .map(|id| CampaignLevel {
+                    id: id.to_string(),
+                    handle: Handle::default(),
+                })
+                .collect(),
+        };
+        Campaign {
+            worlds: vec![world("First", &["a", "b"]), world("Second", &["c"])],
+        }
+    }
+
+    fn next_id(campaign: &Campaign, progress: &CampaignProgress) -> String {
+        campaign.next_level(progress).unwrap().1.id.clone()
+    }
+
+    #[test]
+    fn completing_a_level_unlocks_the_next() {
+        let campaign = campaign();
+        let mut progress = CampaignProgress::default();
+        assert_eq!(next_id(&campaign, &progress), "a");
+        assert!(progress.complete("a"));
+        assert!(!progress.complete("a"));
+        assert_eq!(next_id(&campaign, &progress), "b");
+        progress.complete("b");
+        let (world, level) = campaign.next_level(&progress).unwrap();
+        assert_eq!((world.name.as_str(), level.id.as_str()), ("Second", "c"));
+        progress.complete("c");
+        assert_eq!(next_id(&campaign, &progress), "a");
+    }
+
+    #[test]
+    fn campaign_levels_exist_and_can_be_completed() {
+        let source = include_str!("../../assets/levels/main.campaign.ron");
+        let definition: CampaignDefinition = ron::from_str(source).unwrap();
+        for id in definition.worlds.iter().flat_map(|world| &world.levels) {
+            let path = format!("assets/levels/{id}.level.ron");
+            let level: Level = ron::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            level.validate().unwrap();
+            let problem = solver::Problem::from_level(&level);
+            assert!(solver::is_solvable(&problem), "{path}");
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Undo and redo for the editor. Every change to the level is an [`Edit`],
+//! and applying one gives back the edit that reverts it.
+
+use std::{collections::VecDeque, mem};
+
+use bevy::prelude::*;
+
+use super::{add_gear, cycle_color, cycle_motor, remove_gear, resize_gear};
+use crate::game::level::{GearDefinition, Level};
+
+/// How many edits can be undone.
+pub const HISTORY_LIMIT: usize = 200;
+
+/// A change to a level. Gears are referred to by their index.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    AddGear(Vec2),
+    RemoveGear(usize),
+    MoveGear {
+        index: usize,
+        position: Vec2,
+    },
+    ResizeGear {
+        index: usize,
+        change: i32,
+    },
+    CycleMotor(usize),
+    CycleColor(usize),
+    ToggleInitialStep(usize),
+    SetStart(usize),
+    SetGoal(usize),
+    Rename(String),
+    /// Replace the gear at `index`.
+    SetGear {
+        index: usize,
+        gear: GearDefinition,
+    },
+    /// Put back a removed gear, along with where the player started and the goal.
+    InsertGear {
+        index: usize,
+        gear: GearDefinition,
+        start: usize,
+        goal: usize,
+    },
+}
+
+impl Edit {
+    /// Make the change to `level`. Returns the edit that reverts it, or `None`
+    /// if nothing changed.
+    pub fn apply(self, level: &mut Level) -> Option<Edit> {
+        match self {
+            Edit::AddGear(position) => Some(Edit::RemoveGear(add_gear(level, position))),
+            Edit::RemoveGear(index) => {
+                let gear = level.gears.get(index)?.clone();
+                let (start, goal) = (level.player.gear, level.goal);
+                remove_gear(level, index).then_some(Edit::InsertGear {
+                    index,
+                    gear,
+                    start,
+                    goal,
+                })
+            }
+            Edit::MoveGear { index, position } => {
+                change_gear(level, index, |gear| gear.position = position)
+            }
+            Edit::ResizeGear { index, change } => {
+                change_level_gear(level, index, |level| resize_gear(level, index, change))
+            }
+            Edit::CycleMotor(index) => {
+                change_level_gear(level, index, |level| cycle_motor(level, index))
+            }
+            Edit::CycleColor(index) => {
+                change_level_gear(level, index, |level| cycle_color(level, index))
+            }
+            Edit::ToggleInitialStep(index) => {
+                change_gear(level, index, |gear| gear.initial_step = !gear.initial_step)
+            }
+            Edit::SetStart(index) => {
+                if index >= level.gears.len() {
+                    return None;
+                }
+                let previous = mem::replace(&mut level.player.gear, index);
+                (previous != index).then_some(Edit::SetStart(previous))
+            }
+            Edit::SetGoal(index) => {
+                if index >= level.gears.len() {
+                    return None;
+                }
+                let previous = mem::replace(&mut level.goal, index);
+                (previous != index).then_some(Edit::SetGoal(previous))
+            }
+            Edit::Rename(name) => {
+                let previous = mem::replace(&mut level.name, name);
+                (previous != level.name).then_some(Edit::Rename(previous))
+            }
+            Edit::SetGear { index, gear } => change_gear(level, index, |previous| *previous = gear),
+            Edit::InsertGear {
+                index,
+                gear,
+                start,
+                goal,
+            } => {
+                level.gears.insert(index, gear);
+                level.player.gear = start;
+                level.goal = goal;
+                Some(Edit::RemoveGear(index))
+            }
+        }
+    }
+}
+
+fn change_gear(
+    level: &mut Level,
+    index: usize,
+    change: impl FnOnce(&mut GearDefinition),
+) -> Option<Edit> {
+    change_level_gear(level, index, |level| change(&mut level.gears[index]))
+}
+
+/// Make a change to the gear at `index` and return the edit that puts it back.
+/// Does nothing if there's no such gear.
+fn change_level_gear(
+    level: &mut Level,
+    index: usize,
+    change: impl FnOnce(&mut Level),
+) -> Option<Edit> {
+    let previous = level.gears.get(index)?.clone();
+    change(level);
+    (level.gears[index] != previous).then_some(Edit::SetGear {
+        index,
+        gear: previous,
+    })
+}
+
+/// The edits that can be undone and redone. Only the last [`HISTORY_LIMIT`]
+/// edits are kept.
+#[derive(Debug)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::with_limit(HISTORY_LIMIT)
+    }
+}
+
+impl History {
+    pub fn with_limit(limit: usize) -> History {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit,
+        }
+    }
+
+    /// Make the change to `level` so that it can be undone. Returns whether
+    /// anything changed.
+    pub fn apply(&mut self, level: &mut Level, edit: Edit) -> bool {
+        let Some(revert) = edit.apply(level) else {
+            return false;
+        };
+        self.redo.clear();
+        self.push_undo(revert);
+        true
+    }
+
+    /// Revert the last edit. Returns whether there was one.
+    pub fn undo(&mut self, level: &mut Level) -> bool {
+        let Some(edit) = self.undo.pop_back() else {
+            return false;
+        };
+        if let Some(revert) = edit.apply(level) {
+            self.redo.push(revert);
+        }
+        true
+    }
+
+    /// Make the last undone edit again. Returns whether there was one.
+    pub fn redo(&mut self, level: &mut Level) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        if let Some(revert) = edit.apply(level) {
+            self.push_undo(revert);
+        }
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, edit: Edit) {
+        if self.undo.len() == self.limit {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::game::editor::new_level;
+
+    fn random_edit(rng: &mut StdRng, level: &Level) -> Edit {
+        let index = rng.gen_range(0..level.gears.len());
+        match rng.gen_range(0..11) {
+            0 | 1 => Edit::AddGear(Vec2::new(
+                rng.gen_range(-600.0..600.0),
+                rng.gen_range(-300.0..300.0),
+            )),
+            2 => Edit::RemoveGear(index),
+            3 => Edit::MoveGear {
+                index,
+                position: Vec2::new(rng.gen_range(-600.0..600.0), 0.0),
+            },
+            4 => Edit::ResizeGear {
+                index,
+                change: rng.gen_range(-8..8),
+            },
+            5 => Edit::CycleMotor(index),
+            6 => Edit::CycleColor(index),
+            7 => Edit::ToggleInitialStep(index),
+            8 => Edit::SetStart(index),
+            9 => Edit::Rename(format!("Level {index}")),
+            _ => Edit::SetGoal(index),
+        }
+    }
+
+    fn serialize(level: &Level) -> String {
+        ron::to_string(level).unwrap()
+    }
+
+    #[test]
+    fn undoing_every_edit_restores_the_level() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut level = new_level();
+            let original = serialize(&level);
+            let mut history = History::default();
+            for _ in 0..50 {
+                let edit = random_edit(&mut rng, &level);
+                history.apply(&mut level, edit);
+            }
+            let edited = serialize(&level);
+
+            while history.undo(&mut level) {}
+            assert_eq!(serialize(&level), original, "seed {seed}");
+            while history.redo(&mut level) {}
+            assert_eq!(serialize(&level), edited, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn new_edits_drop_undone_ones() {
+        let mut level = new_level();
+        let mut history = History::default();
+        history.apply(&mut level, Edit::AddGear(Vec2::X * 200.0));
+        history.undo(&mut level);
+        history.apply(&mut level, Edit::CycleColor(0));
+        assert!(!history.redo(&mut level));
+        assert_eq!(level.gears.len(), 1);
+    }
+
+    #[test]
+    fn only_the_last_edits_are_kept() {
+        let mut level = new_level();
+        let mut history = History::with_limit(3);
+        for _ in 0..5 {
+            history.apply(&mut level, Edit::CycleColor(0));
+        }
+        let mut undone = 0;
+        while history.undo(&mut level) {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+    }
+
+    #[test]
+    fn edits_to_missing_gears_do_nothing() {
+        let mut level = new_level();
+        let original = serialize(&level);
+        for edit in [
+            Edit::MoveGear {
+                index: 1,
+                position: Vec2::X,
+            },
+            Edit::ResizeGear {
+                index: 1,
+                change: 2,
+            },
+            Edit::CycleMotor(1),
+            Edit::CycleColor(1),
+            Edit::ToggleInitialStep(1),
+            Edit::SetStart(1),
+            Edit::SetGoal(1),
+            Edit::SetGear {
+                index: 1,
+                gear: level.gears[0].clone(),
+            },
+        ] {
+            assert!(edit.apply(&mut level).is_none());
+        }
+        assert_eq!(serialize(&level), original);
+    }
+}
@@ -0,0 +1,360 @@
+//! Changes a designer can make to a level in the editor. Gears are referred to
+//! by their index in [`Level::gears`], like the level file does.
+
+pub mod history;
+
+use std::{f32::consts::PI, fmt};
+#[cfg(not(target_family = "wasm"))]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use thiserror::Error;
+
+use crate::game::{
+    level::{GearDefinition, Level, LevelError, PlayerDefinition},
+    mechanics::{
+        gear::{gear_radius, RotationDirection, BASE_GEAR_TEETH, MIN_GEAR_TEETH},
+        gear_train::{meshing_distance, MESH_TOLERANCE},
+    },
+};
+
+/// Gears bigger than this don't fit on the screen.
+pub const MAX_GEAR_TEETH: u32 = 36;
+
+/// How close a gear has to be to meshing with another gear to snap to it.
+pub const SNAP_RANGE: f32 = 40.0;
+/// Gears snapped with angle snapping sit at multiples of this angle around
+/// their neighbour.
+pub const SNAP_ANGLE: f32 = PI / 12.0;
+
+/// The colors new gears cycle through.
+pub const GEAR_COLORS: [Srgba; 6] = [
+    Srgba::rgb(0.412, 1.0, 0.917),
+    Srgba::rgb(0.919, 0.971, 0.463),
+    Srgba::rgb(1.0, 0.625, 0.625),
+    Srgba::rgb(0.725, 0.62, 1.0),
+    Srgba::rgb(1.0, 0.78, 0.42),
+    Srgba::rgb(0.86, 0.86, 0.86),
+];
+
+/// A level with a single motor gear to start editing from.
+pub fn new_level() -> Level {
+    Level {
+        name: "Untitled".to_string(),
+        gears: vec![GearDefinition {
+            position: Vec2::ZERO,
+            motor: Some(RotationDirection::Clockwise),
+            color: GEAR_COLORS[0],
+            teeth: BASE_GEAR_TEETH,
+            initial_step: false,
+        }],
+        player: PlayerDefinition {
+            gear: 0,
+            initial_step: false,
+        },
+        goal: 0,
+        allow_jammed: false,
+    }
+}
+
+/// The topmost gear covering `point`, if any.
+pub fn gear_at(level: &Level, point: Vec2) -> Option<usize> {
+    level
+        .gears
+        .iter()
+        .rposition(|gear| gear.position.distance(point) <= gear_radius(gear.teeth))
+}
+
+/// Add a gear at `position` and return its index. It takes the next color
+/// after the last gear's.
+pub fn add_gear(level: &mut Level, position: Vec2) -> usize {
+    let color = level
+        .gears
+        .last()
+        .map_or(GEAR_COLORS[0], |gear| next_color(gear.color));
+    level.gears.push(GearDefinition {
+        position,
+        motor: None,
+        color,
+        teeth: BASE_GEAR_TEETH,
+        initial_step: false,
+    });
+    level.gears.len() - 1
+}
+
+/// Remove the gear at `index`. The last gear can't be removed. If the player
+/// started or the goal was on it, they move to the first gear.
+pub fn remove_gear(level: &mut Level, index: usize) -> bool {
+    if level.gears.len() <= 1 || index >= level.gears.len() {
+        return false;
+    }
+    level.gears.remove(index);
+    for gear in [&mut level.player.gear, &mut level.goal] {
+        if *gear == index {
+            *gear = 0;
+        } else if *gear > index {
+            *gear -= 1;
+        }
+    }
+    true
+}
+
+/// Add `change` teeth to the gear at `index`, within the sizes gears can have.
+pub fn resize_gear(level: &mut Level, index: usize, change: i32) {
+    let gear = &mut level.gears[index];
+    gear.teeth = gear
+        .teeth
+        .saturating_add_signed(change)
+        .clamp(MIN_GEAR_TEETH, MAX_GEAR_TEETH);
+}
+
+/// Switch the gear at `index` from no motor to turning clockwise, counter
+/// clockwise and back.
+pub fn cycle_motor(level: &mut Level, index: usize) {
+    let motor = &mut level.gears[index].motor;
+    *motor = match motor {
+        None => Some(RotationDirection::Clockwise),
+        Some(RotationDirection::Clockwise) => Some(RotationDirection::CounterClockwise),
+        Some(RotationDirection::CounterClockwise) => None,
+    };
+}
+
+/// Give the gear at `index` the next color in [`GEAR_COLORS`].
+pub fn cycle_color(level: &mut Level, index: usize) {
+    let gear = &mut level.gears[index];
+    gear.color = next_color(gear.color);
+}
+
+fn next_color(color: Srgba) -> Srgba {
+    let index = GEAR_COLORS.iter().position(|preset| *preset == color);
+    GEAR_COLORS[index.map_or(0, |index| (index + 1) % GEAR_COLORS.len())]
+}
+
+/// Where to put the gear at `index` when it's moved to `position`, so that it
+/// meshes exactly with the closest gear within [`SNAP_RANGE`]. With
+/// `snap_angle`, it's also turned around that gear to the closest multiple of
+/// [`SNAP_ANGLE`]. Returns the gear it meshes with and the snapped position.
+pub fn snap_position(
+    level: &Level,
+    index: usize,
+    position: Vec2,
+    snap_angle: bool,
+) -> Option<(usize, Vec2)> {
+    let teeth = level.gears[index].teeth;
+    let (neighbour, gear, distance) = level
+        .gears
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != index)
+        .map(|(other, gear)| (other, gear, meshing_distance(teeth, gear.teeth)))
+        .filter(|(_, gear, distance)| {
+            (gear.position.distance(position) - distance).abs() <= SNAP_RANGE
+        })
+        .min_by(|(_, a, a_distance), (_, b, b_distance)| {
+            let a_off = (a.position.distance(position) - a_distance).abs();
+            let b_off = (b.position.distance(position) - b_distance).abs();
+            a_off.total_cmp(&b_off)
+        })?;
+
+    let mut angle = (position - gear.position).to_angle();
+    if snap_angle {
+        angle = (angle / SNAP_ANGLE).round() * SNAP_ANGLE;
+    }
+    Some((
+        neighbour,
+        gear.position + distance * Vec2::from_angle(angle),
+    ))
+}
+
+/// Something off about how two gears are placed, by their indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlacementWarning {
+    /// The teeth of the gears run into each other.
+    Overlapping(usize, usize),
+    /// The gears almost mesh, but are too far apart to drive each other.
+    TooFar(usize, usize),
+}
+
+impl fmt::Display for PlacementWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlacementWarning::Overlapping(a, b) => write!(f, "gears {a} and {b} overlap"),
+            PlacementWarning::TooFar(a, b) => {
+                write!(f, "gears {a} and {b} are too far apart to mesh")
+            }
+        }
+    }
+}
+
+/// Every pair of gears that overlap or are just too far apart to mesh.
+pub fn placement_warnings(level: &Level) -> Vec<PlacementWarning> {
+    let mut warnings = Vec::new();
+    for (i, a) in level.gears.iter().enumerate() {
+        for (j, b) in level.gears.iter().enumerate().skip(i + 1) {
+            let off = a.position.distance(b.position) - meshing_distance(a.teeth, b.teeth);
+            if off < -MESH_TOLERANCE {
+                warnings.push(PlacementWarning::Overlapping(i, j));
+            } else if off > MESH_TOLERANCE && off <= SNAP_RANGE {
+                warnings.push(PlacementWarning::TooFar(i, j));
+            }
+        }
+    }
+    warnings
+}
+
+#[derive(Debug, Error)]
+pub enum LevelFileError {
+    #[error("could not access level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write level file: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("invalid level: {0}")]
+    Invalid(#[from] LevelError),
+}
+
+/// Write `level` to a level file in `directory`, named after the level.
+/// Returns the path of the file.
+#[cfg(not(target_family = "wasm"))]
+pub fn save(level: &Level, directory: impl AsRef<Path>) -> Result<PathBuf, LevelFileError> {
+    level.validate()?;
+    let source = ron::ser::to_string_pretty(level, default())?;
+    let directory = directory.as_ref();
+    fs::create_dir_all(directory)?;
+    let path = directory.join(format!("{}.level.ron", level.file_stem()));
+    fs::write(&path, source)?;
+    Ok(path)
+}
+
+/// Read the level file at `path`.
+#[cfg(not(target_family = "wasm"))]
+pub fn load(path: impl AsRef<Path>) -> Result<Level, LevelFileError> {
+    let level: Level = ron::from_str(&fs::read_to_string(path)?)?;
+    level.validate()?;
+    Ok(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::mechanics::gear_train::neighbours;
+
+    fn three_gears() -> Level {
+        let mut level = new_level();
+        add_gear(&mut level, Vec2::new(180.0, 0.0));
+        add_gear(&mut level, Vec2::new(-180.0, 0.0));
+        level.player.gear = 1;
+        level.goal = 2;
+        level
+    }
+
+    #[test]
+    fn new_levels_are_valid() {
+        new_level().validate().unwrap();
+        three_gears().validate().unwrap();
+    }
+
+    #[test]
+    fn removing_gears_keeps_references_valid() {
+        let mut level = three_gears();
+        assert!(remove_gear(&mut level, 0));
+        assert_eq!((level.player.gear, level.goal), (0, 1));
+        assert_eq!(level.gears[1].position, Vec2::new(-180.0, 0.0));
+
+        assert!(remove_gear(&mut level, 1));
+        assert_eq!((level.player.gear, level.goal), (0, 0));
+        assert!(!remove_gear(&mut level, 0));
+        level.validate().unwrap();
+    }
+
+    #[test]
+    fn the_topmost_gear_is_picked() {
+        let mut level = three_gears();
+        let overlapping = add_gear(&mut level, Vec2::new(150.0, 0.0));
+        assert_eq!(gear_at(&level, Vec2::new(160.0, 0.0)), Some(overlapping));
+        assert_eq!(gear_at(&level, Vec2::new(270.0, 0.0)), Some(1));
+        assert_eq!(gear_at(&level, Vec2::new(0.0, 300.0)), None);
+    }
+
+    #[test]
+    fn gears_stay_within_their_size_limits() {
+        let mut level = new_level();
+        resize_gear(&mut level, 0, -100);
+        assert_eq!(level.gears[0].teeth, MIN_GEAR_TEETH);
+        resize_gear(&mut level, 0, 100);
+        assert_eq!(level.gears[0].teeth, MAX_GEAR_TEETH);
+    }
+
+    #[test]
+    fn snapped_gears_mesh() {
+        let mut level = new_level();
+        let index = add_gear(&mut level, Vec2::ZERO);
+        level.gears[index].teeth = 8;
+        let distance = meshing_distance(BASE_GEAR_TEETH, 8);
+
+        let near = Vec2::new(distance + 20.0, 10.0);
+        let (neighbour, snapped) = snap_position(&level, index, near, false).unwrap();
+        assert_eq!(neighbour, 0);
+        assert!((snapped.length() - distance).abs() < 1e-3);
+        assert!((snapped.to_angle() - near.to_angle()).abs() < 1e-3);
+
+        let (_, snapped) = snap_position(&level, index, near, true).unwrap();
+        assert!(snapped.abs_diff_eq(Vec2::new(distance, 0.0), 1e-3));
+
+        level.gears[index].position = snapped;
+        assert!(placement_warnings(&level).is_empty());
+        assert_eq!(neighbours(&level.train())[index], [0]);
+
+        let far = Vec2::new(distance + SNAP_RANGE + 1.0, 0.0);
+        assert_eq!(snap_position(&level, index, far, false), None);
+    }
+
+    #[test]
+    fn misplaced_gears_are_warned_about() {
+        let mut level = new_level();
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        add_gear(&mut level, Vec2::new(distance - 20.0, 0.0));
+        add_gear(&mut level, Vec2::new(-distance - 20.0, 0.0));
+        add_gear(&mut level, Vec2::new(0.0, 1000.0));
+        assert_eq!(
+            placement_warnings(&level),
+            [
+                PlacementWarning::Overlapping(0, 1),
+                PlacementWarning::TooFar(0, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn saved_levels_load_back() {
+        let directory = crate::game::save::storage::test_directory("editor");
+        let mut level = three_gears();
+        level.name = "Saved / Loaded".to_string();
+        let path = save(&level, &directory).unwrap();
+        assert_eq!(path, directory.join("Saved___Loaded.level.ron"));
+
+        let loaded = load(&path).unwrap();
+        assert_eq!(loaded.name, level.name);
+        assert_eq!(loaded.gears.len(), level.gears.len());
+        assert_eq!((loaded.player.gear, loaded.goal), (1, 2));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn motors_and_colors_cycle_around() {
+        let mut level = new_level();
+        let original = level.gears[0].clone();
+        for _ in 0..3 {
+            cycle_motor(&mut level, 0);
+        }
+        for _ in 0..GEAR_COLORS.len() {
+            cycle_color(&mut level, 0);
+        }
+        assert_eq!(level.gears[0].motor, original.motor);
+        assert_eq!(level.gears[0].color, original.color);
+    }
+}
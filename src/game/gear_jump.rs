@@ -1,41 +1,140 @@
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
 use crate::{
     game::{
-        rotational_movement::{RevolutionMovement, RotationalMovement},
+        actions::{action_just_pressed, InputAction},
+        mechanics::{
+            capture_zone::CaptureZone,
+            gear::RotationalMovement,
+            jump::{self, JumpOutcome, JumpTarget},
+            player::{FreeFlight, RevolutionMovement},
+        },
+        simulation::{action_taken, simulation_paused, ActionQueue, Interpolated, PlayerAction},
         spawn::{
-            gear::{Gear, GearBoundingBox},
+            gear::{Gear, GoalGear},
             player::Player,
         },
     },
     screen::GameButtonAction,
     ui::interaction::InteractionQuery,
-    AppSet,
+    AppSet, SimSet,
 };
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
+        record_jump
+            .in_set(AppSet::RecordInput)
+            .run_if(action_just_pressed(InputAction::Jump).or_else(run_if_interaction_query))
+            .run_if(not(simulation_paused)),
+    );
+    app.add_systems(
+        FixedUpdate,
         detect_collision_move
-            .in_set(AppSet::Update)
-            .run_if(input_just_pressed(KeyCode::Space).or_else(run_if_interaction_query)),
+            .in_set(SimSet::Act)
+            .run_if(action_taken(PlayerAction::Jump)),
     );
 }
 
-fn detect_collision_move(
-    gears: Query<(&Transform, &RotationalMovement, &GearBoundingBox), With<Gear>>,
-    mut player: Query<&mut RevolutionMovement, With<Player>>,
+/// Triggered when the player lands on the [`GoalGear`].
+#[derive(Event, Debug)]
+pub struct LevelCompleted;
+
+/// Triggered when the player tries to jump off the gear `from`.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerJumped {
+    pub from: Entity,
+}
+
+/// Triggered when the player lands on the gear `to`, either right after jumping
+/// or after flying off of the gear they jumped from.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerLanded {
+    pub to: Entity,
+}
+
+/// Triggered right after [`PlayerJumped`] when the jump doesn't reach another
+/// gear, and the player slips off.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct JumpMissed;
+
+fn record_jump(mut queue: ResMut<ActionQueue>) {
+    queue.0.push(PlayerAction::Jump);
+}
+
+/// The gears the player can land on, where the simulation put them. Their
+/// [`Transform`] is blended for drawing, so it depends on the frame rate.
+pub(super) type GearQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Interpolated,
+        &'static RotationalMovement,
+        &'static CaptureZone,
+    ),
+    With<Gear>,
+>;
+
+/// The gears to jump onto, by entity.
+pub(super) fn jump_targets<'a>(
+    gears: &'a GearQuery,
+) -> impl Iterator<Item = (Entity, JumpTarget<'a>)> {
+    gears
+        .iter()
+        .map(|(entity, interpolated, movement, capture_zone)| {
+            (
+                entity,
+                JumpTarget {
+                    position: interpolated.translation.xy(),
+                    movement,
+                    capture_zone,
+                },
+            )
+        })
+}
+
+/// The gear centered at `position`, like the one the player is anchored to.
+pub(super) fn gear_at(gears: &GearQuery, position: Vec2) -> Option<Entity> {
+    gears
+        .iter()
+        .find(|(_, interpolated, _, _)| interpolated.translation.xy() == position)
+        .map(|(entity, ..)| entity)
+}
+
+/// Tell everyone the player landed, and whether that completed the level.
+pub(super) fn trigger_landing(
+    commands: &mut Commands,
+    goals: &Query<(), With<GoalGear>>,
+    to: Entity,
 ) {
-    let mut player_movement = player.single_mut();
-    let position = player_movement.position.xy();
-    let anchor = player_movement.anchor.xy();
+    commands.trigger(PlayerLanded { to });
+    if goals.contains(to) {
+        commands.trigger(LevelCompleted);
+    }
+}
 
-    for (gear_transform, gear_rotation, &GearBoundingBox(bounding_box)) in &gears {
-        let gear_pos = gear_transform.translation.xy();
+fn detect_collision_move(
+    mut commands: Commands,
+    gears: GearQuery,
+    goals: Query<(), With<GoalGear>>,
+    mut player: Query<(Entity, &mut RevolutionMovement), (With<Player>, Without<FreeFlight>)>,
+) {
+    // There's nothing to jump off of while flying.
+    let Ok((entity, mut player_movement)) = player.get_single_mut() else {
+        return;
+    };
+    let Some(from) = gear_at(&gears, player_movement.anchor.xy()) else {
+        return;
+    };
 
-        if (gear_pos != anchor) && (bounding_box.contains(position)) {
-            let player_z = player_movement.anchor.z;
-            player_movement.move_onto_gear(gear_pos.extend(player_z), gear_rotation);
+    commands.trigger(PlayerJumped { from });
+    match jump::jump(jump_targets(&gears), &mut player_movement) {
+        JumpOutcome::Landed(to) => trigger_landing(&mut commands, &goals, to),
+        // Missed! Slip off the current gear.
+        JumpOutcome::Missed(flight) => {
+            commands.entity(entity).insert(flight);
+            commands.trigger(JumpMissed);
         }
     }
 }
@@ -0,0 +1,194 @@
+//! Generate random levels that can always be completed.
+//! The generator lays out a chain of meshed gears from the start to the goal,
+//! and then adds decoy gears branching off of that chain. No gear meshes with
+//! more than its neighbours in the tree, so the gear train can never jam.
+//! Every layout is played through by the solver, and the ones it can't beat
+//! are thrown away.
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::{
+    level::{GearDefinition, Level, PlayerDefinition},
+    mechanics::{
+        gear::RotationDirection,
+        gear_train::{meshing_distance, TrainGear, MESH_TOLERANCE},
+    },
+    solver::{self, Problem},
+};
+
+/// Gear centers stay inside this distance from the origin, so the whole level
+/// fits on screen.
+const PLAYFIELD_HALF_SIZE: Vec2 = Vec2::new(480.0, 210.0);
+/// How often to try placing a single gear before starting over.
+const PLACEMENT_ATTEMPTS: usize = 32;
+/// How often to start over before giving up.
+const LAYOUT_ATTEMPTS: usize = 1000;
+
+#[derive(Debug, Clone)]
+pub struct GeneratorSettings {
+    /// Number of jumps between the start and the goal.
+    pub jumps: usize,
+    /// Number of gears that don't lead to the goal.
+    pub decoys: usize,
+    pub min_teeth: u32,
+    pub max_teeth: u32,
+}
+
+impl GeneratorSettings {
+    /// Settings that get harder with increasing `difficulty`, starting at 0.
+    pub fn with_difficulty(difficulty: u32) -> Self {
+        let difficulty = difficulty.min(5) as usize;
+        Self {
+            jumps: 2 + difficulty,
+            decoys: difficulty,
+            min_teeth: 12 - difficulty as u32,
+            max_teeth: 12 + difficulty as u32,
+        }
+    }
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self::with_difficulty(1)
+    }
+}
+
+/// Generate a level from `seed`. The same seed and settings always produce the
+/// same level. Returns `None` if the settings don't fit into the playfield.
+pub fn generate(seed: u64, settings: &GeneratorSettings) -> Option<Level> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..LAYOUT_ATTEMPTS).find_map(|_| {
+        let gears = generate_layout(&mut rng, settings)?;
+        let gears = gears
+            .into_iter()
+            .map(|gear| GearDefinition {
+                position: gear.position,
+                motor: gear.motor,
+                color: Hsla::hsl(rng.gen_range(0.0..360.0), 0.9, 0.75).into(),
+                teeth: gear.teeth,
+                initial_step: false,
+            })
+            .collect();
+
+        let level = Level {
+            name: format!("Generated #{seed}"),
+            gears,
+            player: PlayerDefinition {
+                gear: 0,
+                initial_step: false,
+            },
+            goal: settings.jumps,
+            allow_jammed: false,
+        };
+        solver::is_solvable(&Problem::from_level(&level)).then_some(level)
+    })
+}
+
+/// Lay out the chain to the goal first, followed by the decoys.
+fn generate_layout(rng: &mut ChaCha8Rng, settings: &GeneratorSettings) -> Option<Vec<TrainGear>> {
+    let motor = if rng.gen() {
+        RotationDirection::Clockwise
+    } else {
+        RotationDirection::CounterClockwise
+    };
+    let mut gears = vec![TrainGear {
+        position: Vec2::new(
+            rng.gen_range(-PLAYFIELD_HALF_SIZE.x..=PLAYFIELD_HALF_SIZE.x),
+            rng.gen_range(-PLAYFIELD_HALF_SIZE.y..=PLAYFIELD_HALF_SIZE.y),
+        ),
+        teeth: rng.gen_range(settings.min_teeth..=settings.max_teeth),
+        motor: Some(motor),
+    }];
+
+    for _ in 0..settings.jumps {
+        let gear = place_next_to(rng, settings, &gears, gears.len() - 1)?;
+        gears.push(gear);
+    }
+    for _ in 0..settings.decoys {
+        let parent = rng.gen_range(0..gears.len());
+        let gear = place_next_to(rng, settings, &gears, parent)?;
+        gears.push(gear);
+    }
+
+    Some(gears)
+}
+
+/// Find a spot for a new gear that meshes with `gears[parent]` and stays clear of all others.
+fn place_next_to(
+    rng: &mut ChaCha8Rng,
+    settings: &GeneratorSettings,
+    gears: &[TrainGear],
+    parent: usize,
+) -> Option<TrainGear> {
+    let playfield = Rect::from_corners(-PLAYFIELD_HALF_SIZE, PLAYFIELD_HALF_SIZE);
+    (0..PLACEMENT_ATTEMPTS).find_map(|_| {
+        let teeth = rng.gen_range(settings.min_teeth..=settings.max_teeth);
+        let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+        let gear = TrainGear {
+            position: gears[parent].position
+                + meshing_distance(gears[parent].teeth, teeth) * direction,
+            teeth,
+            motor: None,
+        };
+
+        let clear_of_others = gears.iter().enumerate().all(|(i, other)| {
+            i == parent
+                || gear.position.distance(other.position)
+                    > meshing_distance(gear.teeth, other.teeth) + MESH_TOLERANCE
+        });
+        (playfield.contains(gear.position) && clear_of_others).then_some(gear)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::solver::Objective;
+
+    #[test]
+    fn generated_levels_are_solvable() {
+        for difficulty in 0..=5 {
+            let settings = GeneratorSettings::with_difficulty(difficulty);
+            for seed in 0..200 {
+                let level = generate(seed, &settings).expect("settings should fit");
+                assert!(level.validate().is_ok(), "seed {seed} is invalid");
+                let problem = Problem::from_level(&level);
+                assert!(
+                    solver::solve(&problem, Objective::FewestJumps).is_some(),
+                    "seed {seed} is unsolvable"
+                );
+                assert_eq!(level.gears.len(), 1 + settings.jumps + settings.decoys);
+            }
+        }
+    }
+
+    #[test]
+    fn generation_is_deterministic() {
+        let settings = GeneratorSettings::default();
+        for seed in 0..100 {
+            let a = generate(seed, &settings).unwrap();
+            let b = generate(seed, &settings).unwrap();
+            let positions = |level: &Level| -> Vec<_> {
+                level.gears.iter().map(|gear| gear.position).collect()
+            };
+            assert_eq!(positions(&a), positions(&b));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_levels() {
+        let settings = GeneratorSettings::default();
+        let a = generate(1, &settings).unwrap();
+        let b = generate(2, &settings).unwrap();
+        assert_ne!(a.gears[1].position, b.gears[1].position);
+    }
+
+    #[test]
+    fn levels_need_a_turning_gear() {
+        let mut level = generate(0, &GeneratorSettings::default()).unwrap();
+        level.gears[0].motor = None;
+        assert!(!solver::is_solvable(&Problem::from_level(&level)));
+    }
+}
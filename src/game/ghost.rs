@@ -0,0 +1,219 @@
+//! A translucent copy of the player that re-runs the best completed attempt
+//! on the current level, to race against. It's simulated from the recorded
+//! inputs like the player is from live ones, so it stays in sync with the gears.
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        actions::{action_just_pressed, InputAction},
+        assets::{HandleMap, ImageKey},
+        campaign::CampaignIds,
+        gear_jump::{jump_targets, GearQuery},
+        level::Level,
+        mechanics::{
+            jump::{self, JumpOutcome},
+            player::{has_fallen, FreeFlight, RevolutionMovement},
+        },
+        replay::{BestRuns, Playback, ReplayInput},
+        rotational_movement::{AngularVelocity, OwnSpeed},
+        save::SaveStorage,
+        simulation::{Interpolated, PlayerAction, SimulationTick},
+        slip::fall_bounds,
+        spawn::{
+            level::{player_start, SpawnLevel},
+            player::sootboi_layout,
+        },
+    },
+    screen::Screen,
+    AppSet, SimSet,
+};
+
+/// The ghost is drawn behind the player.
+const GHOST_Z: f32 = 4.0;
+const GHOST_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<GhostSettings>();
+    app.observe(spawn_ghost);
+    app.add_systems(FixedUpdate, apply_ghost_inputs.in_set(SimSet::Act));
+    app.add_systems(
+        FixedUpdate,
+        (land_ghost_from_free_flight, detect_ghost_fall)
+            .chain()
+            .in_set(SimSet::Collide),
+    );
+    app.add_systems(
+        Update,
+        toggle_ghost.in_set(AppSet::RecordInput).run_if(
+            in_state(Screen::Playing).and_then(action_just_pressed(InputAction::ToggleGhost)),
+        ),
+    );
+}
+
+#[derive(Resource, Debug)]
+pub struct GhostSettings {
+    pub visible: bool,
+}
+
+impl Default for GhostSettings {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+impl GhostSettings {
+    fn visibility(&self) -> Visibility {
+        if self.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Ghost {
+    inputs: Vec<ReplayInput>,
+    /// Index of the next input to apply.
+    next: usize,
+    /// Where to put the ghost when it falls.
+    start: RevolutionMovement,
+}
+
+fn spawn_ghost(
+    trigger: Trigger<SpawnLevel>,
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    campaign_ids: CampaignIds,
+    mut best_runs: ResMut<BestRuns>,
+    storage: Res<SaveStorage>,
+    playback: Option<Res<Playback>>,
+    settings: Res<GhostSettings>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Racing against yourself while watching yourself would be confusing.
+    if playback.is_some() {
+        return;
+    }
+    let Some(level) = levels.get(&trigger.event().0) else {
+        return;
+    };
+    let Some(best_run) = campaign_ids
+        .get(&trigger.event().0)
+        .and_then(|id| best_runs.get_or_load(storage.0.as_ref(), id))
+    else {
+        return;
+    };
+
+    let movement = player_start(level).movement(GHOST_Z);
+    commands.spawn((
+        Name::new("Ghost"),
+        Ghost {
+            inputs: best_run.inputs.clone(),
+            next: 0,
+            start: movement.clone(),
+        },
+        SpriteBundle {
+            texture: image_handles[&ImageKey::Sootboi].clone_weak(),
+            transform: Transform::from_scale(Vec2::splat(0.2).extend(1.0))
+                .with_translation(movement.position),
+            sprite: Sprite {
+                color: GHOST_COLOR,
+                ..default()
+            },
+            visibility: settings.visibility(),
+            ..default()
+        },
+        TextureAtlas {
+            layout: texture_atlas_layouts.add(sootboi_layout()),
+            index: 0,
+        },
+        Interpolated::new(movement.position, 0.0),
+        movement,
+        OwnSpeed(best_run.speed),
+        StateScoped(Screen::Playing),
+    ));
+}
+
+fn apply_ghost_inputs(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    gears: GearQuery,
+    mut ghosts: Query<(
+        Entity,
+        &mut Ghost,
+        &mut RevolutionMovement,
+        &mut OwnSpeed,
+        Has<FreeFlight>,
+    )>,
+) {
+    for (entity, mut ghost, mut movement, mut speed, flying) in &mut ghosts {
+        while let Some(&input) = ghost.inputs.get(ghost.next) {
+            if input.tick > tick.0 {
+                break;
+            }
+            ghost.next += 1;
+            if input.tick < tick.0 {
+                continue;
+            }
+
+            match input.action {
+                // Same as the player's jump, but reaching the goal doesn't complete the level.
+                PlayerAction::Jump if !flying => {
+                    if let JumpOutcome::Missed(flight) =
+                        jump::jump(jump_targets(&gears), &mut movement)
+                    {
+                        commands.entity(entity).insert(flight);
+                    }
+                }
+                PlayerAction::Jump => {}
+                action => speed.0 = AngularVelocity::after(speed.0, action),
+            }
+        }
+    }
+}
+
+fn land_ghost_from_free_flight(
+    mut commands: Commands,
+    gears: GearQuery,
+    mut ghosts: Query<(Entity, &mut RevolutionMovement), (With<Ghost>, With<FreeFlight>)>,
+) {
+    for (entity, mut movement) in &mut ghosts {
+        if jump::catch(jump_targets(&gears), &mut movement).is_some() {
+            commands.entity(entity).remove::<FreeFlight>();
+        }
+    }
+}
+
+fn detect_ghost_fall(
+    mut commands: Commands,
+    mut ghosts: Query<(
+        Entity,
+        &Ghost,
+        &mut RevolutionMovement,
+        &mut Interpolated,
+        &FreeFlight,
+    )>,
+) {
+    let bounds = fall_bounds();
+    for (entity, ghost, mut movement, mut interpolated, flight) in &mut ghosts {
+        // Start over, like the player did when they fell during this run.
+        if has_fallen(bounds, &movement, flight) {
+            commands.entity(entity).remove::<FreeFlight>();
+            *movement = ghost.start.clone();
+            *interpolated = Interpolated::new(movement.position, 0.0);
+        }
+    }
+}
+
+fn toggle_ghost(
+    mut settings: ResMut<GhostSettings>,
+    mut ghosts: Query<&mut Visibility, With<Ghost>>,
+) {
+    settings.visible = !settings.visible;
+    for mut visibility in &mut ghosts {
+        *visibility = settings.visibility();
+    }
+}
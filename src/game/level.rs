@@ -0,0 +1,178 @@
+//! The level file format and its asset loader.
+//! Levels live in `assets/levels/*.level.ron` and describe the gears and
+//! where the player starts, so they can be authored without recompiling.
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::game::mechanics::{
+    gear::{RotationDirection, BASE_GEAR_TEETH, MIN_GEAR_TEETH},
+    gear_train::{self, TrainGear},
+};
+
+/// A level as described by a level file.
+#[derive(Asset, TypePath, Debug, Clone, Serialize, Deserialize)]
+pub struct Level {
+    /// Human readable name of the level.
+    pub name: String,
+    /// All gears in the level. Other parts of the level refer to gears by
+    /// their index in this list.
+    pub gears: Vec<GearDefinition>,
+    /// Where the player starts.
+    pub player: PlayerDefinition,
+    /// Index of the gear the player has to reach to complete the level.
+    pub goal: usize,
+    /// Jammed gears are usually a mistake, so they are rejected unless the
+    /// level uses them on purpose.
+    #[serde(default)]
+    pub allow_jammed: bool,
+}
+
+/// A single gear in a [`Level`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GearDefinition {
+    pub position: Vec2,
+    /// Motor gears turn in the given direction on their own and drive the
+    /// gears meshed with them.
+    #[serde(default)]
+    pub motor: Option<RotationDirection>,
+    pub color: Srgba,
+    /// The size of the gear. Defaults to the size of `gear.png`.
+    #[serde(default = "default_teeth")]
+    pub teeth: u32,
+    /// Start the gear rotated by one step, so its teeth mesh with a
+    /// neighbouring gear that starts unrotated.
+    #[serde(default)]
+    pub initial_step: bool,
+}
+
+fn default_teeth() -> u32 {
+    BASE_GEAR_TEETH
+}
+
+/// The player's starting state in a [`Level`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerDefinition {
+    /// Index of the gear the player starts on.
+    pub gear: usize,
+    #[serde(default)]
+    pub initial_step: bool,
+}
+
+impl Level {
+    /// The level's gears as seen by the gear train, in the same order.
+    pub fn train(&self) -> Vec<TrainGear> {
+        self.gears
+            .iter()
+            .map(|gear| TrainGear {
+                position: gear.position,
+                teeth: gear.teeth,
+                motor: gear.motor,
+            })
+            .collect()
+    }
+
+    /// A file name for the level without extension, made from its name.
+    pub fn file_stem(&self) -> String {
+        file_stem(&self.name)
+    }
+
+    /// Check that the level is internally consistent.
+    pub fn validate(&self) -> Result<(), LevelError> {
+        if self.gears.is_empty() {
+            return Err(LevelError::NoGears);
+        }
+        if self.player.gear >= self.gears.len() {
+            return Err(LevelError::PlayerGearOutOfRange {
+                gear: self.player.gear,
+                count: self.gears.len(),
+            });
+        }
+        if let Some((gear, definition)) = self
+            .gears
+            .iter()
+            .enumerate()
+            .find(|(_, definition)| definition.teeth < MIN_GEAR_TEETH)
+        {
+            return Err(LevelError::TooFewTeeth {
+                gear,
+                teeth: definition.teeth,
+            });
+        }
+        if self.goal >= self.gears.len() {
+            return Err(LevelError::GoalOutOfRange {
+                gear: self.goal,
+                count: self.gears.len(),
+            });
+        }
+        if !self.allow_jammed {
+            let jammed = gear_train::jammed_gears(&gear_train::propagate(&self.train()));
+            if !jammed.is_empty() {
+                return Err(LevelError::Jammed(jammed));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A file name without extension for something called `name`.
+pub fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// A level that parsed fine but doesn't make sense.
+#[derive(Debug, Error)]
+pub enum LevelError {
+    #[error("the level has no gears")]
+    NoGears,
+    #[error("the player starts on gear {gear}, but the level only has {count} gears")]
+    PlayerGearOutOfRange { gear: usize, count: usize },
+    #[error("gear {gear} has {teeth} teeth, but gears need at least {MIN_GEAR_TEETH}")]
+    TooFewTeeth { gear: usize, teeth: u32 },
+    #[error("the goal is gear {gear}, but the level only has {count} gears")]
+    GoalOutOfRange { gear: usize, count: usize },
+    #[error("gears {0:?} are jammed, set `allow_jammed: true` if that's on purpose")]
+    Jammed(Vec<usize>),
+}
+
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse level file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("invalid level: {0}")]
+    Invalid(#[from] LevelError),
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Level, LevelLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let level = ron::de::from_bytes::<Level>(&bytes)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
@@ -0,0 +1,112 @@
+//! The region around a gear in which the player can grab onto it.
+
+use bevy::{math::primitives::Annulus, prelude::*};
+
+use super::gear::{gear_radius, orbit_radius};
+
+/// A ring around a gear, relative to the gear's center and in world units. The
+/// player can land on the gear while they're inside of it. Bigger gears get
+/// bigger zones from [`CaptureZone::of_gear`], rather than from the scale of
+/// the gear's sprite, which is only how it's drawn.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct CaptureZone(pub Annulus);
+
+impl CaptureZone {
+    /// The band around an orbit of `orbit_radius` that is just wide enough to
+    /// reach the rim of a gear of `gear_radius`.
+    pub fn around_orbit(gear_radius: f32, orbit_radius: f32) -> Self {
+        let reach = (gear_radius - orbit_radius).abs();
+        Self(Annulus::new(
+            (orbit_radius - reach).max(0.0),
+            orbit_radius + reach,
+        ))
+    }
+
+    /// The zone of a gear with `teeth` teeth.
+    pub fn of_gear(teeth: u32) -> Self {
+        Self::around_orbit(gear_radius(teeth), orbit_radius(teeth))
+    }
+
+    /// Whether `point` is inside the zone of a gear at `center`. The zone is
+    /// round, so the gear's rotation doesn't matter, and it's already sized
+    /// for the gear, so neither does its scale.
+    pub fn contains(&self, center: Vec2, point: Vec2) -> bool {
+        self.contains_local(point - center)
+    }
+
+    /// Whether the `point` relative to the gear's center is inside the zone.
+    pub fn contains_local(&self, point: Vec2) -> bool {
+        let distance_squared = point.length_squared();
+        self.0.inner_circle.radius.powi(2) <= distance_squared
+            && distance_squared <= self.0.outer_circle.radius.powi(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::mechanics::gear::BASE_GEAR_TEETH;
+
+    #[test]
+    fn around_orbit_reaches_the_rim() {
+        let zone = CaptureZone::around_orbit(100.0, 92.0);
+        assert_eq!(zone.0.inner_circle.radius, 84.0);
+        assert_eq!(zone.0.outer_circle.radius, 100.0);
+    }
+
+    #[test]
+    fn around_orbit_never_has_a_negative_radius() {
+        let zone = CaptureZone::around_orbit(100.0, 20.0);
+        assert_eq!(zone.0.inner_circle.radius, 0.0);
+        assert_eq!(zone.0.outer_circle.radius, 100.0);
+    }
+
+    #[test]
+    fn contains_local_excludes_the_hole() {
+        let zone = CaptureZone(Annulus::new(10.0, 20.0));
+        assert!(!zone.contains_local(Vec2::ZERO));
+        assert!(!zone.contains_local(Vec2::new(9.9, 0.0)));
+        assert!(zone.contains_local(Vec2::new(10.0, 0.0)));
+        assert!(zone.contains_local(Vec2::new(0.0, -15.0)));
+        assert!(zone.contains_local(Vec2::new(20.0, 0.0)));
+        assert!(!zone.contains_local(Vec2::new(20.1, 0.0)));
+    }
+
+    #[test]
+    fn contains_local_has_no_corners() {
+        let zone = CaptureZone(Annulus::new(0.0, 20.0));
+        // Inside the axis-aligned bounding box, but outside the circle.
+        assert!(!zone.contains_local(Vec2::new(19.0, 19.0)));
+    }
+
+    #[test]
+    fn zones_grow_with_the_gear() {
+        let base = CaptureZone::of_gear(BASE_GEAR_TEETH);
+        let double = CaptureZone::of_gear(2 * BASE_GEAR_TEETH);
+        assert_eq!(
+            double.0.inner_circle.radius,
+            2.0 * base.0.inner_circle.radius
+        );
+        assert_eq!(
+            double.0.outer_circle.radius,
+            2.0 * base.0.outer_circle.radius
+        );
+        let rim = Vec2::new(gear_radius(2 * BASE_GEAR_TEETH), 0.0);
+        assert!(double.contains(Vec2::ZERO, rim));
+        assert!(!base.contains(Vec2::ZERO, rim));
+    }
+
+    #[test]
+    fn contains_follows_the_center() {
+        let zone = CaptureZone(Annulus::new(10.0, 20.0));
+        let center = Vec2::new(100.0, -50.0);
+        assert!(zone.contains(center, Vec2::new(115.0, -50.0)));
+        assert!(!zone.contains(center, Vec2::new(15.0, 0.0)));
+        for angle in [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0] {
+            let offset = Vec2::from_angle(angle);
+            assert!(zone.contains(center, center + 15.0 * offset));
+            assert!(!zone.contains(center, center + 25.0 * offset));
+        }
+    }
+}
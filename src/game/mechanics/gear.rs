@@ -0,0 +1,137 @@
+//! The size and spin of gears.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::kinematics::angle_of;
+
+/// Rotation in degrees per second of a gear with [`BASE_GEAR_TEETH`] teeth.
+const DEFAULT_GEAR_ROTATION: f32 = 30.0;
+
+/// Number of teeth on a gear of the base size, which is how `gear.png` is
+/// drawn. Gears with more or fewer teeth are scaled relative to it.
+pub const BASE_GEAR_TEETH: u32 = 12;
+/// Outer radius of a gear of the base size.
+pub const BASE_GEAR_RADIUS: f32 = 100.0;
+/// Gears with fewer teeth than this are too small to stand on.
+pub const MIN_GEAR_TEETH: u32 = 6;
+/// How far from the center the player orbits, relative to the gear's outer radius.
+const ORBIT_RADIUS_RATIO: f32 = 0.92;
+/// Radius of the circle on which the teeth of meshed gears touch, relative to
+/// the gear's outer radius. Two gears mesh when their pitch circles touch.
+const PITCH_RADIUS_RATIO: f32 = 0.9;
+
+#[derive(Debug, Reflect, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationDirection {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl RotationDirection {
+    /// Angular velocity of a gear with `teeth` teeth. All gears turn with the
+    /// same speed at their rim, like meshed gears do, so smaller gears spin faster.
+    /// `None` means the gear stands still.
+    pub fn angular_velocity(direction: Option<Self>, teeth: u32) -> f32 {
+        DEFAULT_GEAR_ROTATION.to_radians() * BASE_GEAR_TEETH as f32 / teeth as f32
+            * match direction {
+                Some(RotationDirection::Clockwise) => -1.0,
+                Some(RotationDirection::CounterClockwise) => 1.0,
+                None => 0.0,
+            }
+    }
+
+    pub fn opposite(&self) -> RotationDirection {
+        match self {
+            RotationDirection::Clockwise => RotationDirection::CounterClockwise,
+            RotationDirection::CounterClockwise => RotationDirection::Clockwise,
+        }
+    }
+}
+
+/// Outer radius of a gear with `teeth` teeth.
+pub fn gear_radius(teeth: u32) -> f32 {
+    BASE_GEAR_RADIUS * teeth as f32 / BASE_GEAR_TEETH as f32
+}
+
+/// Radius of the pitch circle of a gear with `teeth` teeth.
+pub fn pitch_radius(teeth: u32) -> f32 {
+    gear_radius(teeth) * PITCH_RADIUS_RATIO
+}
+
+/// Radius the player orbits at on a gear with `teeth` teeth.
+pub fn orbit_radius(teeth: u32) -> f32 {
+    gear_radius(teeth) * ORBIT_RADIUS_RATIO
+}
+
+/// Angle between a tooth and the gap next to it on a gear with `teeth` teeth.
+pub fn half_tooth_angle(teeth: u32) -> f32 {
+    PI / teeth as f32
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct RotationalMovement {
+    angular_velocity: f32,
+    /// `None` if the gear isn't connected to a motor.
+    direction: Option<RotationDirection>,
+    teeth: u32,
+    include_initial_step: bool,
+    /// How far the gear has turned, wrapped to one revolution.
+    rotation: f32,
+}
+
+impl RotationalMovement {
+    pub fn new(
+        direction: Option<RotationDirection>,
+        teeth: u32,
+        include_initial_step: bool,
+    ) -> RotationalMovement {
+        let mut movement = RotationalMovement {
+            angular_velocity: RotationDirection::angular_velocity(direction, teeth),
+            direction,
+            teeth,
+            include_initial_step,
+            rotation: 0.0,
+        };
+        movement.rotation = movement.get_initial_rotation();
+        movement
+    }
+
+    pub fn get_initial_rotation(&self) -> f32 {
+        if self.include_initial_step {
+            half_tooth_angle(self.teeth)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    pub fn angular_velocity(&self) -> f32 {
+        self.angular_velocity
+    }
+
+    pub fn teeth(&self) -> u32 {
+        self.teeth
+    }
+
+    /// Turn the gear for `delta` seconds at `speed`.
+    pub fn turn(&mut self, speed: f32, delta: f32) {
+        self.rotation = (self.rotation + self.angular_velocity * speed * delta).rem_euclid(TAU);
+    }
+
+    /// The rotation of a player landing on this gear at `point`, when it's at
+    /// `anchor`. They snap half a tooth ahead, onto the next tooth.
+    pub fn landing_rotation(&self, anchor: Vec2, point: Vec2) -> f32 {
+        angle_of(point, anchor)
+            + match self.direction {
+                Some(RotationDirection::CounterClockwise) => -half_tooth_angle(self.teeth),
+                Some(RotationDirection::Clockwise) => half_tooth_angle(self.teeth),
+                None => 0.0,
+            }
+    }
+}
@@ -0,0 +1,286 @@
+//! Work out how the gears in a level drive each other.
+//! Gears whose pitch circles touch are meshed. Motor gears turn on their own
+//! and every gear meshed with a turning gear turns the opposite way.
+//! Gears that aren't connected to a motor stand still.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::gear::{pitch_radius, RotationDirection};
+
+/// How far the distance between two gears can be off from their meshing
+/// distance while still meshing, in pixels.
+pub const MESH_TOLERANCE: f32 = 5.0;
+
+/// A gear as far as the gear train is concerned.
+#[derive(Debug, Clone, Copy)]
+pub struct TrainGear {
+    pub position: Vec2,
+    pub teeth: u32,
+    /// The direction this gear turns in if it's a motor.
+    pub motor: Option<RotationDirection>,
+}
+
+/// The distance between the centers of two meshed gears.
+pub fn meshing_distance(teeth_a: u32, teeth_b: u32) -> f32 {
+    pitch_radius(teeth_a) + pitch_radius(teeth_b)
+}
+
+/// Whether the teeth of two gears are in contact.
+pub fn meshes(a: &TrainGear, b: &TrainGear) -> bool {
+    let distance = a.position.distance(b.position);
+    (distance - meshing_distance(a.teeth, b.teeth)).abs() <= MESH_TOLERANCE
+}
+
+/// The indices of the gears meshed with each gear.
+pub fn neighbours(gears: &[TrainGear]) -> Vec<Vec<usize>> {
+    let mut neighbours = vec![Vec::new(); gears.len()];
+    for (i, a) in gears.iter().enumerate() {
+        for (j, b) in gears.iter().enumerate().skip(i + 1) {
+            if meshes(a, b) {
+                neighbours[i].push(j);
+                neighbours[j].push(i);
+            }
+        }
+    }
+    neighbours
+}
+
+/// How a gear moves as part of the gear train.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum GearDrive {
+    /// Driven by a motor, directly or through other gears.
+    Turning(RotationDirection),
+    /// Not connected to a motor.
+    Idle,
+    /// Part of a train that can't turn: it has a cycle with an odd number of
+    /// gears, or motors that work against each other.
+    Jammed,
+}
+
+impl GearDrive {
+    /// The direction the gear turns in, or `None` if it stands still.
+    pub fn direction(&self) -> Option<RotationDirection> {
+        match self {
+            GearDrive::Turning(direction) => Some(*direction),
+            GearDrive::Idle | GearDrive::Jammed => None,
+        }
+    }
+}
+
+/// How every gear moves. Meshed gears turn in opposite directions, so a
+/// connected train can only turn if its gears can be split into two groups
+/// that only mesh with each other, and all motors agree on which group turns
+/// which way. Otherwise the whole train is jammed.
+pub fn propagate(gears: &[TrainGear]) -> Vec<GearDrive> {
+    let neighbours = neighbours(gears);
+    let mut drives = vec![GearDrive::Idle; gears.len()];
+    // Whether each visited gear turns the same way as the first gear of its train.
+    let mut same_as_root: Vec<Option<bool>> = vec![None; gears.len()];
+
+    for root in 0..gears.len() {
+        if same_as_root[root].is_some() {
+            continue;
+        }
+
+        let mut train = vec![root];
+        let mut jammed = false;
+        same_as_root[root] = Some(true);
+        let mut queue = VecDeque::from([root]);
+        while let Some(i) = queue.pop_front() {
+            let parity = same_as_root[i].unwrap();
+            for &j in &neighbours[i] {
+                match same_as_root[j] {
+                    None => {
+                        same_as_root[j] = Some(!parity);
+                        train.push(j);
+                        queue.push_back(j);
+                    }
+                    Some(other) => jammed |= other == parity,
+                }
+            }
+        }
+
+        // Every motor dictates a direction for the root gear, and they all have to agree.
+        let mut root_direction = None;
+        for &i in &train {
+            let Some(motor) = gears[i].motor else {
+                continue;
+            };
+            let implied = if same_as_root[i].unwrap() {
+                motor
+            } else {
+                motor.opposite()
+            };
+            match root_direction {
+                None => root_direction = Some(implied),
+                Some(direction) => jammed |= direction != implied,
+            }
+        }
+
+        for &i in &train {
+            drives[i] = match root_direction {
+                _ if jammed => GearDrive::Jammed,
+                None => GearDrive::Idle,
+                Some(direction) if same_as_root[i].unwrap() => GearDrive::Turning(direction),
+                Some(direction) => GearDrive::Turning(direction.opposite()),
+            };
+        }
+    }
+
+    drives
+}
+
+/// The indices of all gears that are jammed.
+pub fn jammed_gears(drives: &[GearDrive]) -> Vec<usize> {
+    drives
+        .iter()
+        .enumerate()
+        .filter(|(_, drive)| **drive == GearDrive::Jammed)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::mechanics::gear::BASE_GEAR_TEETH;
+
+    fn gear(x: f32, teeth: u32, motor: Option<RotationDirection>) -> TrainGear {
+        TrainGear {
+            position: Vec2::new(x, 0.0),
+            teeth,
+            motor,
+        }
+    }
+
+    #[test]
+    fn meshed_gears_turn_opposite_ways() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let drives = propagate(&[
+            gear(0.0, BASE_GEAR_TEETH, Some(RotationDirection::Clockwise)),
+            gear(distance, BASE_GEAR_TEETH, None),
+            gear(2.0 * distance, BASE_GEAR_TEETH, None),
+        ]);
+        assert_eq!(
+            drives,
+            [
+                GearDrive::Turning(RotationDirection::Clockwise),
+                GearDrive::Turning(RotationDirection::CounterClockwise),
+                GearDrive::Turning(RotationDirection::Clockwise),
+            ]
+        );
+    }
+
+    #[test]
+    fn gears_of_different_sizes_mesh() {
+        let small = gear(0.0, 8, Some(RotationDirection::CounterClockwise));
+        let large = gear(meshing_distance(8, 24), 24, None);
+        assert!(meshes(&small, &large));
+        assert_eq!(
+            propagate(&[small, large])[1],
+            GearDrive::Turning(RotationDirection::Clockwise)
+        );
+    }
+
+    #[test]
+    fn unconnected_gears_stand_still() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let drives = propagate(&[
+            gear(0.0, BASE_GEAR_TEETH, Some(RotationDirection::Clockwise)),
+            gear(distance + 2.0 * MESH_TOLERANCE, BASE_GEAR_TEETH, None),
+        ]);
+        assert_eq!(drives[1], GearDrive::Idle);
+    }
+
+    #[test]
+    fn odd_cycles_jam() {
+        // Three gears in a triangle, each meshed with the other two.
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let corner = |angle: f32| TrainGear {
+            position: distance / f32::sqrt(3.0) * Vec2::from_angle(angle.to_radians()),
+            teeth: BASE_GEAR_TEETH,
+            motor: None,
+        };
+        let mut gears = [corner(90.0), corner(210.0), corner(330.0)];
+        gears[0].motor = Some(RotationDirection::Clockwise);
+        gears[1].position.y -= 100.0;
+
+        // Broken open, the chain turns.
+        assert_eq!(jammed_gears(&propagate(&gears)), Vec::<usize>::new());
+
+        gears[1] = corner(210.0);
+        assert_eq!(propagate(&gears), [GearDrive::Jammed; 3]);
+    }
+
+    #[test]
+    fn odd_cycles_jam_without_motors() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let gears = [0.0, 120.0, 240.0].map(|angle: f32| TrainGear {
+            position: distance / f32::sqrt(3.0) * Vec2::from_angle(angle.to_radians()),
+            teeth: BASE_GEAR_TEETH,
+            motor: None,
+        });
+        assert_eq!(jammed_gears(&propagate(&gears)), [0, 1, 2]);
+    }
+
+    #[test]
+    fn even_cycles_turn() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let gears = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(x, y)| TrainGear {
+            position: distance * Vec2::new(x, y),
+            teeth: BASE_GEAR_TEETH,
+            motor: (x == 0.0 && y == 0.0).then_some(RotationDirection::Clockwise),
+        });
+        assert_eq!(
+            propagate(&gears)
+                .iter()
+                .map(GearDrive::direction)
+                .collect::<Vec<_>>(),
+            [
+                Some(RotationDirection::Clockwise),
+                Some(RotationDirection::CounterClockwise),
+                Some(RotationDirection::Clockwise),
+                Some(RotationDirection::CounterClockwise),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicting_motors_jam() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let drives = propagate(&[
+            gear(0.0, BASE_GEAR_TEETH, Some(RotationDirection::Clockwise)),
+            gear(distance, BASE_GEAR_TEETH, None),
+            gear(
+                2.0 * distance,
+                BASE_GEAR_TEETH,
+                Some(RotationDirection::CounterClockwise),
+            ),
+            // Not connected to the rest, so it keeps turning.
+            gear(
+                10.0 * distance,
+                BASE_GEAR_TEETH,
+                Some(RotationDirection::Clockwise),
+            ),
+        ]);
+        assert_eq!(jammed_gears(&drives), [0, 1, 2]);
+        assert_eq!(drives[3], GearDrive::Turning(RotationDirection::Clockwise));
+    }
+
+    #[test]
+    fn agreeing_motors_turn() {
+        let distance = meshing_distance(BASE_GEAR_TEETH, BASE_GEAR_TEETH);
+        let drives = propagate(&[
+            gear(0.0, BASE_GEAR_TEETH, Some(RotationDirection::Clockwise)),
+            gear(distance, BASE_GEAR_TEETH, None),
+            gear(
+                2.0 * distance,
+                BASE_GEAR_TEETH,
+                Some(RotationDirection::Clockwise),
+            ),
+        ]);
+        assert!(jammed_gears(&drives).is_empty());
+    }
+}
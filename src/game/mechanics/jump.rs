@@ -0,0 +1,131 @@
+//! Jumping from gear to gear. Gears are told apart by keys of the caller's
+//! choosing, like entities in the game or indices in the solver.
+
+use bevy::prelude::*;
+
+use super::{
+    capture_zone::CaptureZone,
+    gear::RotationalMovement,
+    player::{FreeFlight, RevolutionMovement},
+};
+
+/// A gear the player might land on.
+#[derive(Clone, Copy)]
+pub struct JumpTarget<'a> {
+    pub position: Vec2,
+    pub movement: &'a RotationalMovement,
+    pub capture_zone: &'a CaptureZone,
+}
+
+/// Where a jump left the player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpOutcome<K> {
+    /// Landed on the gear with this key.
+    Landed(K),
+    /// Missed, and flying off the gear.
+    Missed(FreeFlight),
+}
+
+/// The first of the `gears` the `player` is in reach of. Skips the gear
+/// they're anchored to, since they're always inside of that one.
+pub fn find_landing<'a, K>(
+    gears: impl IntoIterator<Item = (K, JumpTarget<'a>)>,
+    player: &RevolutionMovement,
+) -> Option<(K, JumpTarget<'a>)> {
+    let position = player.position.xy();
+    let anchor = player.anchor.xy();
+    gears.into_iter().find(|(_, target)| {
+        target.position != anchor && target.capture_zone.contains(target.position, position)
+    })
+}
+
+/// Jump from the `player`'s gear onto the first of the `gears` in reach.
+pub fn jump<'a, K>(
+    gears: impl IntoIterator<Item = (K, JumpTarget<'a>)>,
+    player: &mut RevolutionMovement,
+) -> JumpOutcome<K> {
+    match catch(gears, player) {
+        Some(key) => JumpOutcome::Landed(key),
+        None => JumpOutcome::Missed(FreeFlight::launch(player)),
+    }
+}
+
+/// Land a flying `player` on the first of the `gears` they flew into, if any.
+pub fn catch<'a, K>(
+    gears: impl IntoIterator<Item = (K, JumpTarget<'a>)>,
+    player: &mut RevolutionMovement,
+) -> Option<K> {
+    let (key, target) = find_landing(gears, player)?;
+    let anchor = target.position.extend(player.anchor.z);
+    player.move_onto_gear(anchor, target.movement);
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::mechanics::gear::{RotationDirection, BASE_GEAR_TEETH};
+
+    fn gear() -> (RotationalMovement, CaptureZone) {
+        (
+            RotationalMovement::new(
+                Some(RotationDirection::CounterClockwise),
+                BASE_GEAR_TEETH,
+                false,
+            ),
+            CaptureZone::of_gear(BASE_GEAR_TEETH),
+        )
+    }
+
+    fn player() -> RevolutionMovement {
+        RevolutionMovement::new(
+            Vec3::new(0.0, 0.0, 5.0),
+            Some(RotationDirection::Clockwise),
+            BASE_GEAR_TEETH,
+            false,
+        )
+    }
+
+    #[test]
+    fn jumps_land_on_gears_in_reach() {
+        let (movement, capture_zone) = gear();
+        let near = JumpTarget {
+            position: Vec2::new(180.0, 0.0),
+            movement: &movement,
+            capture_zone: &capture_zone,
+        };
+        let far = JumpTarget {
+            position: Vec2::new(400.0, 0.0),
+            ..near
+        };
+
+        let mut player = player();
+        assert_eq!(
+            jump([("far", far), ("near", near)], &mut player),
+            JumpOutcome::Landed("near")
+        );
+        assert_eq!(player.anchor, Vec3::new(180.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn jumps_out_of_reach_miss() {
+        let (movement, capture_zone) = gear();
+        let start = JumpTarget {
+            position: Vec2::ZERO,
+            movement: &movement,
+            capture_zone: &capture_zone,
+        };
+        let far = JumpTarget {
+            position: Vec2::new(400.0, 0.0),
+            ..start
+        };
+
+        let mut player = player();
+        let before = player.clone();
+        assert_eq!(
+            jump([("start", start), ("far", far)], &mut player),
+            JumpOutcome::Missed(FreeFlight::launch(&before))
+        );
+        assert_eq!(player.anchor, before.anchor);
+    }
+}
@@ -0,0 +1,282 @@
+//! Closed-form motion of the player around a gear.
+//! Angles are in radians, counter-clockwise from the positive x axis. Times
+//! are in seconds at unit speed.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::capture_zone::CaptureZone;
+
+/// The angle of `point` around `anchor`.
+pub fn angle_of(point: Vec2, anchor: Vec2) -> f32 {
+    let offset = point - anchor;
+    offset.y.atan2(offset.x)
+}
+
+/// A circle around `anchor` that is traced at a constant angular velocity.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct Orbit {
+    pub anchor: Vec2,
+    pub radius: f32,
+    pub angular_velocity: f32,
+    /// The angle at time 0.
+    pub rotation: f32,
+}
+
+impl Orbit {
+    pub fn rotation_at(&self, time: f32) -> f32 {
+        self.rotation + self.angular_velocity * time
+    }
+
+    pub fn position_at(&self, time: f32) -> Vec2 {
+        self.anchor + self.radius * Vec2::from_angle(self.rotation_at(time))
+    }
+
+    /// The velocity along the orbit, tangent to it.
+    pub fn velocity_at(&self, time: f32) -> Vec2 {
+        self.angular_velocity * self.radius * Vec2::from_angle(self.rotation_at(time)).perp()
+    }
+
+    /// The time one revolution takes, or `None` if the orbit stands still.
+    pub fn period(&self) -> Option<f32> {
+        (self.angular_velocity != 0.0).then(|| TAU / self.angular_velocity.abs())
+    }
+
+    /// The same orbit, with time 0 moved to `time`.
+    pub fn at(&self, time: f32) -> Self {
+        Self {
+            rotation: self.rotation_at(time).rem_euclid(TAU),
+            ..*self
+        }
+    }
+
+    /// Time until the orbit passes `angle`, or `None` if it never does.
+    pub fn time_until_rotation(&self, angle: f32) -> Option<f32> {
+        let mut distance =
+            (self.angular_velocity.signum() * (angle - self.rotation)).rem_euclid(TAU);
+        // `rem_euclid` rounds tiny negative distances up to a whole turn.
+        if distance >= TAU {
+            distance = 0.0;
+        }
+
+        if distance == 0.0 {
+            Some(0.0)
+        } else if self.angular_velocity == 0.0 {
+            None
+        } else {
+            Some(distance / self.angular_velocity.abs())
+        }
+    }
+
+    /// Time until the orbit enters the capture zone of a gear at `center`, or
+    /// `None` if it never does. Zero if it starts out inside.
+    pub fn time_until_inside(&self, zone: &CaptureZone, center: Vec2) -> Option<f32> {
+        if zone.contains_local(self.position_at(0.0) - center) {
+            return Some(0.0);
+        }
+
+        let offset = center - self.anchor;
+        let distance = offset.length();
+        if self.angular_velocity == 0.0 || self.radius == 0.0 || distance == 0.0 {
+            // The distance to the center never changes.
+            return None;
+        }
+
+        // At an angle `a` away from the center, the squared distance to it is
+        // `radius² + distance² - 2 radius distance cos(a)`. Solve for the
+        // angles where that crosses the inner and outer circles of the zone.
+        let cos_at = |zone_radius: f32| {
+            (self.radius.powi(2) + distance.powi(2) - zone_radius.powi(2))
+                / (2.0 * self.radius * distance)
+        };
+        let max_cos = cos_at(zone.0.inner_circle.radius);
+        let min_cos = cos_at(zone.0.outer_circle.radius);
+        if min_cos > 1.0 || max_cos < -1.0 {
+            return None;
+        }
+        let nearest = max_cos.min(1.0).acos();
+        let farthest = min_cos.max(-1.0).acos();
+
+        // The zone covers the angles between `nearest` and `farthest` on both
+        // sides of the center. Each side is entered from the end the orbit reaches first.
+        let center_angle = offset.to_angle();
+        let entries = if self.angular_velocity > 0.0 {
+            [center_angle + nearest, center_angle - farthest]
+        } else {
+            [center_angle - nearest, center_angle + farthest]
+        };
+        entries
+            .into_iter()
+            .filter_map(|angle| self.time_until_rotation(angle))
+            .min_by(f32::total_cmp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use bevy::math::primitives::Annulus;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    const CASES: usize = 1000;
+    const EPSILON: f32 = 1e-3;
+
+    fn random_orbit(rng: &mut StdRng) -> Orbit {
+        Orbit {
+            anchor: Vec2::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0)),
+            radius: rng.gen_range(10.0..200.0),
+            angular_velocity: rng.gen_range(-3.0..3.0),
+            rotation: rng.gen_range(-10.0..10.0),
+        }
+    }
+
+    /// How far apart two angles are, ignoring whole turns.
+    fn angle_between(a: f32, b: f32) -> f32 {
+        ((a - b + PI).rem_euclid(TAU) - PI).abs()
+    }
+
+    #[test]
+    fn angle_of_inverts_from_angle() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..CASES {
+            let anchor = Vec2::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0));
+            let angle = rng.gen_range(-10.0..10.0);
+            let point = anchor + rng.gen_range(1.0..200.0) * Vec2::from_angle(angle);
+            assert!(angle_between(angle_of(point, anchor), angle) < EPSILON);
+        }
+    }
+
+    #[test]
+    fn angle_of_covers_every_quadrant() {
+        assert_eq!(angle_of(Vec2::X, Vec2::ZERO), 0.0);
+        assert_eq!(angle_of(Vec2::Y, Vec2::ZERO), PI / 2.0);
+        assert_eq!(angle_of(Vec2::NEG_X, Vec2::ZERO), PI);
+        assert_eq!(angle_of(Vec2::NEG_Y, Vec2::ZERO), -PI / 2.0);
+        assert_eq!(angle_of(Vec2::new(5.0, 1.0), Vec2::new(6.0, 1.0)), PI);
+    }
+
+    #[test]
+    fn position_stays_on_the_orbit() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..CASES {
+            let orbit = random_orbit(&mut rng);
+            let time = rng.gen_range(0.0..100.0);
+            let position = orbit.position_at(time);
+            assert!((position.distance(orbit.anchor) - orbit.radius).abs() < EPSILON);
+            assert!(
+                angle_between(angle_of(position, orbit.anchor), orbit.rotation_at(time)) < EPSILON
+            );
+            assert!(orbit.velocity_at(time).dot(position - orbit.anchor).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn rebasing_keeps_the_path() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..CASES {
+            let orbit = random_orbit(&mut rng);
+            let (start, time) = (rng.gen_range(0.0..100.0), rng.gen_range(0.0..10.0));
+            let rebased = orbit.at(start);
+            assert!(
+                rebased
+                    .position_at(time)
+                    .distance(orbit.position_at(start + time))
+                    < 0.1
+            );
+        }
+    }
+
+    #[test]
+    fn time_until_rotation_reaches_the_angle() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..CASES {
+            let orbit = random_orbit(&mut rng);
+            let angle = rng.gen_range(-10.0..10.0);
+            let Some(time) = orbit.time_until_rotation(angle) else {
+                assert_eq!(orbit.angular_velocity, 0.0);
+                continue;
+            };
+            assert!(time >= 0.0);
+            assert!(orbit.period().is_some_and(|period| time <= period));
+            assert!(angle_between(orbit.rotation_at(time), angle) < EPSILON);
+        }
+    }
+
+    #[test]
+    fn standing_still_only_reaches_the_current_angle() {
+        let orbit = Orbit {
+            anchor: Vec2::ZERO,
+            radius: 10.0,
+            angular_velocity: 0.0,
+            rotation: 1.0,
+        };
+        assert_eq!(orbit.time_until_rotation(1.0 + TAU), Some(0.0));
+        assert_eq!(orbit.time_until_rotation(2.0), None);
+        assert_eq!(orbit.period(), None);
+    }
+
+    #[test]
+    fn time_until_inside_finds_the_first_entry() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut entered = 0;
+        for _ in 0..CASES {
+            let orbit = random_orbit(&mut rng);
+            let center = orbit.anchor
+                + Vec2::new(rng.gen_range(-300.0..300.0), rng.gen_range(-300.0..300.0));
+            let inner = rng.gen_range(0.0..100.0);
+            let zone = CaptureZone(Annulus::new(inner, inner + rng.gen_range(1.0..50.0)));
+            // Slightly smaller and larger zones, to stay clear of rounding errors.
+            let shrunk = CaptureZone(Annulus::new(
+                zone.0.inner_circle.radius + EPSILON,
+                zone.0.outer_circle.radius - EPSILON,
+            ));
+            let grown = CaptureZone(Annulus::new(
+                zone.0.inner_circle.radius - EPSILON,
+                zone.0.outer_circle.radius + EPSILON,
+            ));
+
+            let search_time = orbit.period().unwrap_or(1.0);
+            let samples = (0..=1000).map(|i| search_time * i as f32 / 1000.0);
+            match orbit.time_until_inside(&zone, center) {
+                Some(time) => {
+                    entered += 1;
+                    assert!(grown.contains_local(orbit.position_at(time) - center));
+                    for sample in samples.take_while(|sample| *sample < time) {
+                        assert!(!shrunk.contains_local(orbit.position_at(sample) - center));
+                    }
+                }
+                None => {
+                    for sample in samples {
+                        assert!(!shrunk.contains_local(orbit.position_at(sample) - center));
+                    }
+                }
+            }
+        }
+        // Make sure both branches were exercised.
+        assert!(0 < entered && entered < CASES);
+    }
+
+    #[test]
+    fn time_until_inside_is_zero_inside() {
+        let orbit = Orbit {
+            anchor: Vec2::ZERO,
+            radius: 92.0,
+            angular_velocity: 1.0,
+            rotation: 0.0,
+        };
+        let zone = CaptureZone::around_orbit(100.0, 92.0);
+        assert_eq!(
+            orbit.time_until_inside(&zone, Vec2::new(180.0, 0.0)),
+            Some(0.0)
+        );
+        assert_eq!(orbit.time_until_inside(&zone, Vec2::new(500.0, 0.0)), None);
+        let quarter_turn = orbit
+            .time_until_inside(&zone, Vec2::new(0.0, 180.0))
+            .unwrap();
+        assert!(0.0 < quarter_turn && quarter_turn < PI / 2.0);
+    }
+}
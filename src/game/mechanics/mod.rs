@@ -0,0 +1,12 @@
+//! The rules of the game: how gears turn and drive each other, how the player
+//! orbits and jumps between them, and when they fall. Nothing in here draws,
+//! plays sounds or loads assets, so the solver, the generator and tests can
+//! use the rules on their own. The plugins in [`crate::game`] run them every
+//! fixed step and draw the results.
+
+pub mod capture_zone;
+pub mod gear;
+pub mod gear_train;
+pub mod jump;
+pub mod kinematics;
+pub mod player;
@@ -0,0 +1,165 @@
+//! How the player moves: around a gear, or flying off after a missed jump.
+
+use bevy::prelude::*;
+
+use super::{
+    gear::{half_tooth_angle, orbit_radius, RotationDirection, RotationalMovement},
+    kinematics::Orbit,
+};
+
+/// Bounds for the speed the player can set.
+pub const MIN_SPEED: f32 = 0.25;
+pub const MAX_SPEED: f32 = 4.0;
+/// Factor by which every speed change speeds up or slows down.
+const SPEED_STEP: f32 = 2.0;
+
+/// The speed after speeding up from `speed`.
+pub fn faster(speed: f32) -> f32 {
+    f32::min(MAX_SPEED, speed * SPEED_STEP)
+}
+
+/// The speed after slowing down from `speed`.
+pub fn slower(speed: f32) -> f32 {
+    f32::max(MIN_SPEED, speed / SPEED_STEP)
+}
+
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct RevolutionMovement {
+    pub anchor: Vec3,
+    orbit: Orbit,
+    /// Time spent on the current orbit at unit speed, wrapped to one revolution.
+    orbit_time: f32,
+    pub position: Vec3,
+}
+
+impl RevolutionMovement {
+    pub fn new(
+        anchor: Vec3,
+        rotation_direction: Option<RotationDirection>,
+        gear_teeth: u32,
+        initial_gear_step: bool,
+    ) -> Self {
+        let mut movement = Self {
+            anchor,
+            orbit: Orbit {
+                anchor: anchor.xy(),
+                radius: orbit_radius(gear_teeth),
+                angular_velocity: RotationDirection::angular_velocity(
+                    rotation_direction,
+                    gear_teeth,
+                ),
+                rotation: if initial_gear_step {
+                    half_tooth_angle(gear_teeth)
+                } else {
+                    0.0
+                },
+            },
+            orbit_time: 0.0,
+            position: anchor,
+        };
+        movement.position = movement.orbit.position_at(0.0).extend(anchor.z);
+        movement
+    }
+
+    pub fn move_onto_gear(&mut self, anchor: Vec3, rotational_movement: &RotationalMovement) {
+        self.anchor = anchor;
+        self.orbit = Orbit {
+            anchor: anchor.xy(),
+            radius: orbit_radius(rotational_movement.teeth()),
+            angular_velocity: rotational_movement.angular_velocity(),
+            rotation: rotational_movement.landing_rotation(anchor.xy(), self.position.xy()),
+        };
+        self.orbit_time = 0.0;
+    }
+
+    /// The orbit the player is on, starting from where they are now.
+    pub fn orbit(&self) -> Orbit {
+        self.orbit.at(self.orbit_time)
+    }
+
+    /// The velocity of the player along the orbit at unit speed.
+    pub fn tangential_velocity(&self) -> Vec2 {
+        self.orbit.velocity_at(self.orbit_time)
+    }
+
+    pub fn update_position(&mut self, speed: f32, delta: f32) {
+        self.orbit_time += speed * delta;
+        if let Some(period) = self.orbit.period() {
+            self.orbit_time %= period;
+        }
+        self.position = self
+            .orbit
+            .position_at(self.orbit_time)
+            .extend(self.anchor.z);
+    }
+}
+
+/// The player is flying in a straight line after missing a jump.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct FreeFlight {
+    /// Velocity at unit speed, scaled by the speed like the orbits are.
+    pub velocity: Vec2,
+}
+
+impl FreeFlight {
+    /// Launch off the orbit described by `movement`.
+    pub fn launch(movement: &RevolutionMovement) -> Self {
+        Self {
+            velocity: movement.tangential_velocity(),
+        }
+    }
+
+    /// Fly `movement` along for `delta` seconds at `speed`.
+    pub fn advance(&self, movement: &mut RevolutionMovement, speed: f32, delta: f32) {
+        movement.position += (self.velocity * speed * delta).extend(0.0);
+    }
+}
+
+/// Whether a player flying with `flight` has left `bounds` for good.
+pub fn has_fallen(bounds: Rect, movement: &RevolutionMovement, flight: &FreeFlight) -> bool {
+    // Slipping off a gear that stands still means just letting go of it.
+    flight.velocity == Vec2::ZERO || !bounds.contains(movement.position.xy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_stays_in_bounds() {
+        let mut speed = 1.0;
+        for _ in 0..10 {
+            speed = faster(speed);
+        }
+        assert_eq!(speed, MAX_SPEED);
+        for _ in 0..10 {
+            speed = slower(speed);
+        }
+        assert_eq!(speed, MIN_SPEED);
+        assert_eq!(slower(faster(1.0)), 1.0);
+    }
+
+    #[test]
+    fn flying_out_of_bounds_is_falling() {
+        let mut movement =
+            RevolutionMovement::new(Vec3::ZERO, Some(RotationDirection::Clockwise), 12, false);
+        let flight = FreeFlight::launch(&movement);
+        let bounds = Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(200.0));
+        assert!(!has_fallen(bounds, &movement, &flight));
+
+        for _ in 0..100 {
+            flight.advance(&mut movement, 1.0, 0.1);
+        }
+        assert!(has_fallen(bounds, &movement, &flight));
+    }
+
+    #[test]
+    fn letting_go_of_a_still_gear_is_falling() {
+        let movement = RevolutionMovement::new(Vec3::ZERO, None, 12, false);
+        let flight = FreeFlight::launch(&movement);
+        let bounds = Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(200.0));
+        assert!(has_fallen(bounds, &movement, &flight));
+    }
+}
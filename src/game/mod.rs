@@ -2,12 +2,26 @@
 
 use bevy::prelude::*;
 
+pub mod actions;
 mod animation;
 pub mod assets;
 pub mod audio;
+pub mod campaign;
+pub mod editor;
 pub mod gear_jump;
+#[cfg(test)]
+mod generator;
+pub mod ghost;
+pub mod level;
+pub mod mechanics;
 mod movement;
-mod rotational_movement;
+pub mod replay;
+pub mod rotational_movement;
+pub mod save;
+pub mod settings;
+pub mod simulation;
+pub mod slip;
+pub mod solver;
 pub mod spawn;
 
 pub(super) fn plugin(app: &mut App) {
@@ -15,9 +29,25 @@ pub(super) fn plugin(app: &mut App) {
         animation::plugin,
         audio::plugin,
         assets::plugin,
+        save::plugin,
+        settings::plugin,
+        campaign::plugin,
         movement::plugin,
+        gameplay_plugin,
+        replay::plugin,
+        ghost::plugin,
+    ));
+}
+
+/// The parts of the game needed to play a level, without a window, audio or
+/// asset loading. See [`crate::headless`].
+pub(super) fn gameplay_plugin(app: &mut App) {
+    app.add_plugins((
+        actions::plugin,
         rotational_movement::plugin,
         spawn::plugin,
         gear_jump::plugin,
+        slip::plugin,
+        simulation::plugin,
     ));
 }
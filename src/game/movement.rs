@@ -5,6 +5,7 @@
 
 use bevy::{prelude::*, window::PrimaryWindow};
 
+use super::actions::{ActionState, InputAction};
 use crate::AppSet;
 
 pub(super) fn plugin(app: &mut App) {
@@ -30,21 +31,21 @@ pub(super) fn plugin(app: &mut App) {
 pub struct MovementController(pub Vec2);
 
 fn record_movement_controller(
-    input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut controller_query: Query<&mut MovementController>,
 ) {
     // Collect directional input.
     let mut intent = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) || input.pressed(KeyCode::ArrowUp) {
+    if actions.pressed(InputAction::MoveUp) {
         intent.y += 1.0;
     }
-    if input.pressed(KeyCode::KeyS) || input.pressed(KeyCode::ArrowDown) {
+    if actions.pressed(InputAction::MoveDown) {
         intent.y -= 1.0;
     }
-    if input.pressed(KeyCode::KeyA) || input.pressed(KeyCode::ArrowLeft) {
+    if actions.pressed(InputAction::MoveLeft) {
         intent.x -= 1.0;
     }
-    if input.pressed(KeyCode::KeyD) || input.pressed(KeyCode::ArrowRight) {
+    if actions.pressed(InputAction::MoveRight) {
         intent.x += 1.0;
     }
 
@@ -0,0 +1,329 @@
+//! Record the player's input during a level and play it back, to reproduce a
+//! run exactly. This relies on the simulation being deterministic, so inputs
+//! are stored with the fixed step they were applied in.
+
+use std::collections::HashMap;
+#[cfg(not(target_family = "wasm"))]
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    game::{
+        actions::{action_just_pressed, InputAction},
+        gear_jump::LevelCompleted,
+        level::Level,
+        rotational_movement::AngularVelocity,
+        save::{storage::Storage, SaveStorage},
+        simulation::{PlayerAction, SimulationTick, StepActions},
+        spawn::level::SpawnLevel,
+    },
+    screen::Screen,
+    SimSet,
+};
+
+/// The replay format written by this version of the game. Bump this whenever
+/// the format or the simulation changes in a way that breaks old replays.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// The [`Storage`] key of the replay of the last completed or saved run.
+pub const LAST_REPLAY_KEY: &str = "replays/last";
+/// The best run on each level is kept under this [`Storage`] key, followed by
+/// the level's campaign id.
+const BEST_REPLAYS_KEY: &str = "replays/best";
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BestRuns>();
+    app.observe(start_recording);
+    app.observe(finish_recording);
+    app.add_systems(
+        FixedUpdate,
+        (play_back_inputs, record_inputs)
+            .chain()
+            .in_set(SimSet::Replay),
+    );
+    app.add_systems(OnExit(Screen::Playing), stop_playback);
+
+    app.add_systems(
+        Update,
+        save_last_replay.run_if(
+            in_state(Screen::Playing).and_then(action_just_pressed(InputAction::SaveReplay)),
+        ),
+    );
+}
+
+/// Everything needed to reproduce a run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub level: Level,
+    /// The [`AngularVelocity`] at the start of the run.
+    pub speed: f32,
+    /// Sorted by tick.
+    pub inputs: Vec<ReplayInput>,
+    /// The tick the player reached the goal in, if they did.
+    #[serde(default)]
+    pub completion_tick: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayInput {
+    /// The [`SimulationTick`] the action was applied in.
+    pub tick: u64,
+    pub action: PlayerAction,
+}
+
+/// Just enough of a replay file to tell which version it is.
+#[derive(Deserialize)]
+struct ReplayHeader {
+    version: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not access replay file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse replay file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write replay file: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("replay version {0} is not supported, expected version {REPLAY_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("there is no replay yet")]
+    Missing,
+}
+
+impl Replay {
+    pub fn new(level: Level, speed: f32) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            level,
+            speed,
+            inputs: Vec::new(),
+            completion_tick: None,
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ReplayError> {
+        Ok(ron::ser::to_string_pretty(self, default())?)
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, ReplayError> {
+        let header: ReplayHeader = ron::from_str(source)?;
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+        Ok(ron::from_str(source)?)
+    }
+
+    /// Load a replay file from anywhere, like one dropped onto the window.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    /// The replay stored under `key`, or `None` if there is none.
+    pub fn read(storage: &dyn Storage, key: &str) -> Result<Option<Self>, ReplayError> {
+        storage
+            .read(key)?
+            .map(|source| Self::from_ron(&source))
+            .transpose()
+    }
+
+    pub fn write(&self, storage: &dyn Storage, key: &str) -> Result<(), ReplayError> {
+        Ok(storage.write(key, &self.to_ron()?)?)
+    }
+}
+
+/// The run that is being recorded.
+#[derive(Resource, Debug)]
+pub struct Recording(pub Replay);
+
+/// Insert this to play back a replay instead of reading the player's input.
+/// The replay's level has to be spawned separately.
+#[derive(Resource, Debug)]
+pub struct Playback {
+    replay: Replay,
+    /// Index of the next input to apply.
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+}
+
+fn start_recording(
+    trigger: Trigger<SpawnLevel>,
+    mut commands: Commands,
+    levels: Res<Assets<Level>>,
+    playback: Option<Res<Playback>>,
+    mut speed: ResMut<AngularVelocity>,
+) {
+    let Some(level) = levels.get(&trigger.event().0) else {
+        return;
+    };
+    if let Some(playback) = playback {
+        speed.0 = playback.replay.speed;
+    }
+    commands.insert_resource(Recording(Replay::new(level.clone(), speed.0)));
+}
+
+fn play_back_inputs(
+    tick: Res<SimulationTick>,
+    playback: Option<ResMut<Playback>>,
+    mut actions: ResMut<StepActions>,
+) {
+    let Some(mut playback) = playback else {
+        return;
+    };
+    // The player's own input is ignored during playback.
+    actions.0.clear();
+    while let Some(input) = playback.replay.inputs.get(playback.next) {
+        if input.tick > tick.0 {
+            break;
+        }
+        if input.tick == tick.0 {
+            actions.0.push(input.action);
+        }
+        playback.next += 1;
+    }
+}
+
+fn record_inputs(
+    tick: Res<SimulationTick>,
+    actions: Res<StepActions>,
+    recording: Option<ResMut<Recording>>,
+) {
+    let Some(mut recording) = recording else {
+        return;
+    };
+    for &action in &actions.0 {
+        recording.0.inputs.push(ReplayInput {
+            tick: tick.0,
+            action,
+        });
+    }
+}
+
+fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+fn finish_recording(
+    _trigger: Trigger<LevelCompleted>,
+    tick: Res<SimulationTick>,
+    recording: Option<ResMut<Recording>>,
+    storage: Res<SaveStorage>,
+) {
+    let Some(mut recording) = recording else {
+        return;
+    };
+    // Landing on the goal again doesn't make the run any faster.
+    if recording.0.completion_tick.is_some() {
+        return;
+    }
+    recording.0.completion_tick = Some(tick.0);
+
+    save_last_replay(Some(recording.into()), storage);
+}
+
+fn save_last_replay(recording: Option<Res<Recording>>, storage: Res<SaveStorage>) {
+    let Some(recording) = recording else {
+        return;
+    };
+    match recording.0.write(storage.0.as_ref(), LAST_REPLAY_KEY) {
+        Ok(()) => info!("Saved replay"),
+        Err(error) => error!("Could not save replay: {error}"),
+    }
+}
+
+/// The run with the best time on each campaign level, by campaign id. Which
+/// run that is follows the [`LevelRecord`](crate::game::save::LevelRecord)s
+/// in the save data. They are kept in [`Storage`] between sessions.
+#[derive(Resource, Debug, Default)]
+pub struct BestRuns(HashMap<String, Option<Replay>>);
+
+impl BestRuns {
+    /// The best run on the campaign level `id`, if there is one.
+    pub fn get_or_load(&mut self, storage: &dyn Storage, id: &str) -> Option<&Replay> {
+        self.0
+            .entry(id.to_string())
+            .or_insert_with(|| {
+                Replay::read(storage, &best_run_key(id))
+                    .inspect_err(|error| warn!("Ignoring best run on \"{id}\": {error}"))
+                    .ok()
+                    .flatten()
+            })
+            .as_ref()
+    }
+
+    /// Keep `replay` as the best run on the campaign level `id`.
+    pub fn insert(&mut self, storage: &dyn Storage, id: &str, replay: Replay) {
+        if let Err(error) = replay.write(storage, &best_run_key(id)) {
+            error!("Could not save best run: {error}");
+        }
+        self.0.insert(id.to_string(), Some(replay));
+    }
+}
+
+fn best_run_key(id: &str) -> String {
+    format!("{BEST_REPLAYS_KEY}/{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::generator::{self, GeneratorSettings};
+
+    fn replay() -> Replay {
+        let level = generator::generate(0, &GeneratorSettings::default()).unwrap();
+        let mut replay = Replay::new(level, 2.0);
+        replay.inputs = vec![
+            ReplayInput {
+                tick: 3,
+                action: PlayerAction::SpeedUp,
+            },
+            ReplayInput {
+                tick: 70,
+                action: PlayerAction::Jump,
+            },
+        ];
+        replay
+    }
+
+    #[test]
+    fn replays_survive_a_round_trip() {
+        let replay = replay();
+        let loaded = Replay::from_ron(&replay.to_ron().unwrap()).unwrap();
+        assert_eq!(loaded.inputs, replay.inputs);
+        assert_eq!(loaded.speed, replay.speed);
+        assert_eq!(loaded.level.name, replay.level.name);
+        assert_eq!(loaded.to_ron().unwrap(), replay.to_ron().unwrap());
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let mut replay = replay();
+        replay.version = REPLAY_VERSION + 1;
+        assert!(matches!(
+            Replay::from_ron(&replay.to_ron().unwrap()),
+            Err(ReplayError::UnsupportedVersion(version)) if version == REPLAY_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(matches!(
+            Replay::from_ron("(version: 1, level: ())"),
+            Err(ReplayError::Parse(_))
+        ));
+        assert!(matches!(
+            Replay::from_ron("not a replay"),
+            Err(ReplayError::Parse(_))
+        ));
+    }
+}
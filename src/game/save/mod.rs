@@ -0,0 +1,296 @@
+//! Save data that outlives the game: campaign progress, the best results on
+//! each level, the player's settings and their input bindings. It's written
+//! to [`Storage`] whenever it changes.
+
+pub mod storage;
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use self::storage::Storage;
+use crate::game::{
+    actions::ActionMap,
+    campaign::{CampaignIds, CampaignProgress},
+    gear_jump::{LevelCompleted, PlayerJumped},
+    replay::{BestRuns, Playback, Recording},
+    settings::Settings,
+    simulation::{SimulationTick, SIMULATION_HZ},
+    spawn::level::{CurrentLevel, SpawnLevel},
+};
+
+/// The save data format written by this version of the game. Bump this and
+/// migrate the previous version in [`SaveData::from_ron`] when the format
+/// changes in a way old files can't be read as.
+pub const SAVE_VERSION: u32 = 1;
+
+/// The key save data is stored under.
+const SAVE_KEY: &str = "save";
+/// Save data that couldn't be loaded is kept under this key, so it isn't lost
+/// when the defaults that replace it are saved.
+const UNREADABLE_SAVE_KEY: &str = "save.unreadable";
+
+pub(super) fn plugin(app: &mut App) {
+    let storage = storage::default_storage();
+    let save = SaveData::load(storage.as_ref());
+    app.insert_resource(save.bindings.clone());
+    app.insert_resource(save);
+    app.insert_resource(SaveStorage(storage));
+    app.add_systems(
+        Update,
+        (
+            store_bindings
+                .run_if(resource_changed::<ActionMap>.and_then(not(resource_added::<ActionMap>))),
+            write_save_data
+                .run_if(resource_changed::<SaveData>.and_then(not(resource_added::<SaveData>))),
+        )
+            .chain(),
+    );
+
+    app.init_resource::<RunStats>();
+    app.observe(start_run);
+    app.observe(count_jump);
+    app.observe(record_run);
+}
+
+/// Where the save data and other files that outlive the game are kept.
+#[derive(Resource)]
+pub struct SaveStorage(pub Box<dyn Storage>);
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    pub version: u32,
+    pub progress: CampaignProgress,
+    /// The best results on each campaign level, by campaign id.
+    pub records: BTreeMap<String, LevelRecord>,
+    pub settings: Settings,
+    /// A copy of the [`ActionMap`] resource, which is the one to change.
+    pub bindings: ActionMap,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            version: SAVE_VERSION,
+            progress: default(),
+            records: default(),
+            settings: default(),
+            bindings: default(),
+        }
+    }
+}
+
+/// The best results on a level. They can come from different runs. The run
+/// with the best time is kept in [`BestRuns`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelRecord {
+    /// In seconds.
+    pub best_time: Option<f32>,
+    pub fewest_jumps: Option<u32>,
+}
+
+impl LevelRecord {
+    /// Take the results of a completed run into account. Returns which of
+    /// them beat the record.
+    pub fn update(&mut self, time: f32, jumps: u32) -> RecordsBeaten {
+        let mut beaten = RecordsBeaten::default();
+        if self.best_time.is_none_or(|best| time < best) {
+            self.best_time = Some(time);
+            beaten.time = true;
+        }
+        if self.fewest_jumps.is_none_or(|fewest| jumps < fewest) {
+            self.fewest_jumps = Some(jumps);
+            beaten.jumps = true;
+        }
+        beaten
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordsBeaten {
+    pub time: bool,
+    pub jumps: bool,
+}
+
+impl RecordsBeaten {
+    pub fn any(self) -> bool {
+        self.time || self.jumps
+    }
+}
+
+/// Just enough of the save data to tell which version it is.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("could not access save data: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse save data: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not serialize save data: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("save data version {0} is newer than this game's version {SAVE_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+impl SaveData {
+    /// Parse save data of this or an older version.
+    pub fn from_ron(source: &str) -> Result<Self, SaveError> {
+        let header: SaveHeader = ron::from_str(source)?;
+        match header.version {
+            SAVE_VERSION => Ok(ron::from_str(source)?),
+            version => Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        Ok(ron::ser::to_string_pretty(self, default())?)
+    }
+
+    /// Load the save data, or start over with defaults if there is none or it
+    /// can't be read. Unreadable save data is set aside rather than overwritten.
+    pub fn load(storage: &dyn Storage) -> Self {
+        let source = match storage.read(SAVE_KEY) {
+            Ok(Some(source)) => source,
+            Ok(None) => return default(),
+            Err(error) => {
+                warn!("Could not read save data, starting over: {error}");
+                return default();
+            }
+        };
+        Self::from_ron(&source).unwrap_or_else(|error| {
+            warn!("Could not load save data, starting over: {error}");
+            if let Err(error) = storage.write(UNREADABLE_SAVE_KEY, &source) {
+                error!("Could not set the unreadable save data aside: {error}");
+            }
+            default()
+        })
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), SaveError> {
+        Ok(storage.write(SAVE_KEY, &self.to_ron()?)?)
+    }
+}
+
+fn store_bindings(bindings: Res<ActionMap>, mut save: ResMut<SaveData>) {
+    save.bindings.clone_from(&bindings);
+}
+
+fn write_save_data(save: Res<SaveData>, storage: Res<SaveStorage>) {
+    if let Err(error) = save.save(storage.0.as_ref()) {
+        error!("Could not save: {error}");
+    }
+}
+
+/// How the current run on a level is going.
+#[derive(Resource, Debug, Default)]
+struct RunStats {
+    jumps: u32,
+}
+
+fn start_run(_trigger: Trigger<SpawnLevel>, mut stats: ResMut<RunStats>) {
+    *stats = default();
+}
+
+fn count_jump(_trigger: Trigger<PlayerJumped>, mut stats: ResMut<RunStats>) {
+    stats.jumps += 1;
+}
+
+/// Keep the results of a completed run on a campaign level. Watching a
+/// replay or play-testing a level from the editor doesn't count.
+fn record_run(
+    _trigger: Trigger<LevelCompleted>,
+    stats: Res<RunStats>,
+    tick: Res<SimulationTick>,
+    current_level: Option<Res<CurrentLevel>>,
+    campaign_ids: CampaignIds,
+    playback: Option<Res<Playback>>,
+    recording: Option<Res<Recording>>,
+    mut best_runs: ResMut<BestRuns>,
+    storage: Res<SaveStorage>,
+    mut save: ResMut<SaveData>,
+) {
+    if playback.is_some() {
+        return;
+    }
+    let Some(id) = current_level.and_then(|current| campaign_ids.get(&current.0)) else {
+        return;
+    };
+    let time = (tick.0 as f64 / SIMULATION_HZ) as f32;
+    let mut record = save.records.get(id).copied().unwrap_or_default();
+    let beaten = record.update(time, stats.jumps);
+    if beaten.time {
+        if let Some(recording) = recording {
+            let mut replay = recording.0.clone();
+            replay.completion_tick = Some(tick.0);
+            best_runs.insert(storage.0.as_ref(), id, replay);
+        }
+    }
+    // Only touch the save data when there's something new, so it isn't written needlessly.
+    if beaten.any() {
+        info!("New record on \"{id}\": {record:?}");
+        save.records.insert(id.to_string(), record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{storage::MemoryStorage, *};
+
+    #[test]
+    fn save_data_is_loaded_back() {
+        let storage = MemoryStorage::default();
+        let mut save = SaveData::default();
+        save.progress.complete("three_gears");
+        save.records
+            .entry("three_gears".to_string())
+            .or_default()
+            .update(4.5, 2);
+        save.settings.music_volume = 0.8;
+        save.bindings
+            .unbind_all(crate::game::actions::InputAction::Restart);
+        save.save(&storage).unwrap();
+        assert_eq!(SaveData::load(&storage), save);
+    }
+
+    #[test]
+    fn unreadable_save_data_is_set_aside() {
+        let storage = MemoryStorage::default();
+        for source in ["(version: 1, progress: (", "(version: 99)"] {
+            storage.write(SAVE_KEY, source).unwrap();
+            assert_eq!(SaveData::load(&storage), SaveData::default());
+            assert_eq!(
+                storage.read(UNREADABLE_SAVE_KEY).unwrap().as_deref(),
+                Some(source)
+            );
+        }
+    }
+
+    #[test]
+    fn records_keep_the_best_of_each() {
+        let mut record = LevelRecord::default();
+        assert_eq!(
+            record.update(10.0, 5),
+            RecordsBeaten {
+                time: true,
+                jumps: true
+            }
+        );
+        assert_eq!(
+            record.update(12.0, 3),
+            RecordsBeaten {
+                time: false,
+                jumps: true
+            }
+        );
+        assert!(!record.update(11.0, 4).any());
+        assert_eq!(record.best_time, Some(10.0));
+        assert_eq!(record.fewest_jumps, Some(3));
+    }
+}
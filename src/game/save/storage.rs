@@ -0,0 +1,132 @@
+//! Where save data is kept. Native builds write files to the platform's data
+//! directory. Other platforms can plug in their own [`Storage`].
+
+use std::{collections::HashMap, io, sync::Mutex};
+#[cfg(not(target_family = "wasm"))]
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+
+/// Stores text under a key, like files in a directory. Keys can have `/` in
+/// them, like paths.
+pub trait Storage: Send + Sync + 'static {
+    /// What's stored under `key`, or `None` if nothing is.
+    fn read(&self, key: &str) -> io::Result<Option<String>>;
+
+    fn write(&self, key: &str, contents: &str) -> io::Result<()>;
+}
+
+/// The game's directory for files that outlive it, like `~/.local/share/bevy-gear-slip`
+/// on Linux. `None` if the platform doesn't have one.
+#[cfg(not(target_family = "wasm"))]
+pub fn data_dir() -> Option<PathBuf> {
+    let directories = directories::ProjectDirs::from("", "", env!("CARGO_PKG_NAME"))?;
+    Some(directories.data_dir().to_path_buf())
+}
+
+/// Keeps everything in files named after their keys in a directory.
+#[cfg(not(target_family = "wasm"))]
+#[derive(Debug)]
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl FileStorage {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// Storage in the platform's [`data_dir`]. `None` if the platform doesn't have one.
+    pub fn in_data_dir() -> Option<Self> {
+        Some(Self::new(data_dir()?))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{key}.ron"))
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Storage for FileStorage {
+    fn read(&self, key: &str) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.path(key)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn write(&self, key: &str, contents: &str) -> io::Result<()> {
+        let path = self.path(key);
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        // Write to a temporary file first, so a crash can't leave a half-written file behind.
+        let temporary = path.with_extension("ron.tmp");
+        fs::write(&temporary, contents)?;
+        fs::rename(temporary, path)
+    }
+}
+
+/// Keeps everything in memory, so it's lost when the game closes.
+#[derive(Debug, Default)]
+pub struct MemoryStorage(Mutex<HashMap<String, String>>);
+
+impl Storage for MemoryStorage {
+    fn read(&self, key: &str) -> io::Result<Option<String>> {
+        Ok(self.0.lock().unwrap().get(key).cloned())
+    }
+
+    fn write(&self, key: &str, contents: &str) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+/// The best storage available on this platform.
+pub fn default_storage() -> Box<dyn Storage> {
+    #[cfg(not(target_family = "wasm"))]
+    if let Some(storage) = FileStorage::in_data_dir() {
+        return Box::new(storage);
+    }
+    warn!("Save data can't be stored on this platform, it will be lost when the game closes");
+    Box::new(MemoryStorage::default())
+}
+
+/// A new directory for a test to write files to, so tests running at the same
+/// time don't get in each other's way.
+#[cfg(test)]
+pub fn test_directory(name: &str) -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("gear-slip-{name}-{}-{count}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_read_back() {
+        let directory = test_directory("storage");
+        let storage = FileStorage::new(&directory);
+        assert_eq!(storage.read("save").unwrap(), None);
+        storage.write("save", "first").unwrap();
+        storage.write("save", "second").unwrap();
+        assert_eq!(storage.read("save").unwrap().as_deref(), Some("second"));
+        storage.write("replays/last", "replay").unwrap();
+        assert_eq!(
+            storage.read("replays/last").unwrap().as_deref(),
+            Some("replay")
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+//! The player's settings. They're part of the [`SaveData`] and are applied as
+//! soon as they change.
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{mechanics::player, save::SaveData};
+
+/// Volumes are set in steps of this size.
+pub const VOLUME_STEP: f32 = 0.1;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        apply_display_settings.run_if(resource_changed::<SaveData>),
+    );
+}
+
+/// Missing settings take their default, so settings can be added without a
+/// new save data version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// The [`AngularVelocity`](crate::game::rotational_movement::AngularVelocity)
+    /// levels start at.
+    pub speed: f32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Hold still what only moves for decoration.
+    pub reduced_motion: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.3,
+            sfx_volume: 0.3,
+            speed: 1.0,
+            fullscreen: false,
+            vsync: true,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn music(&self) -> Volume {
+        Volume::new(self.music_volume)
+    }
+
+    pub fn sfx(&self) -> Volume {
+        Volume::new(self.sfx_volume)
+    }
+}
+
+/// The speeds a level can start at, from the slowest to the fastest.
+pub fn speeds() -> Vec<f32> {
+    let mut speeds = vec![player::MIN_SPEED];
+    while let Some(&speed) = speeds.last().filter(|speed| **speed < player::MAX_SPEED) {
+        speeds.push(player::faster(speed));
+    }
+    speeds
+}
+
+/// A run condition for decorative motion.
+pub fn reduced_motion(save: Res<SaveData>) -> bool {
+    save.settings.reduced_motion
+}
+
+fn apply_display_settings(
+    save: Res<SaveData>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = window_query.get_single_mut() else {
+        return;
+    };
+    let mode = if save.settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    };
+    let present_mode = if save.settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    // Only touch the window when something changes, as that can make it flicker.
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speeds_go_from_slowest_to_fastest() {
+        assert_eq!(speeds(), [0.25, 0.5, 1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn missing_settings_take_their_default() {
+        let settings: Settings = ron::from_str("(vsync: false, volume: 0.5)").unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                vsync: false,
+                ..default()
+            }
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! The simulation runs in [`FixedUpdate`], so it plays out the same way at any
+//! frame rate. Input is recorded every frame and handed to the next fixed step,
+//! and the sprites are blended between the last two steps when drawn.
+
+use std::{f32::consts::TAU, mem};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppSet, SimSet};
+
+/// Fixed steps per second.
+pub const SIMULATION_HZ: f64 = 64.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ));
+
+    app.init_resource::<SimulationTick>();
+    app.init_resource::<ActionQueue>();
+    app.init_resource::<StepActions>();
+    app.add_systems(
+        FixedUpdate,
+        (advance_tick, take_queued_actions, store_previous_state).in_set(SimSet::Input),
+    );
+
+    app.register_type::<Interpolated>();
+    app.add_systems(Update, interpolate_transforms.in_set(AppSet::Update));
+}
+
+/// The number of the current fixed step, counting from 1 at the start of the level.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimulationTick(pub u64);
+
+fn advance_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+/// Something the player does that affects the simulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+pub enum PlayerAction {
+    Jump,
+    SpeedUp,
+    SpeedDown,
+}
+
+/// Actions recorded since the last fixed step.
+#[derive(Resource, Debug, Default)]
+pub struct ActionQueue(pub Vec<PlayerAction>);
+
+/// Actions to apply during the current fixed step.
+#[derive(Resource, Debug, Default)]
+pub struct StepActions(pub Vec<PlayerAction>);
+
+impl StepActions {
+    pub fn contains(&self, action: PlayerAction) -> bool {
+        self.0.contains(&action)
+    }
+}
+
+/// Run condition for systems that respond to `action` in the current step.
+pub fn action_taken(action: PlayerAction) -> impl Fn(Res<StepActions>) -> bool {
+    move |actions: Res<StepActions>| actions.contains(action)
+}
+
+/// Run condition for whether the game is paused. Input for the simulation
+/// isn't recorded then.
+pub fn simulation_paused(time: Res<Time<Virtual>>) -> bool {
+    time.is_paused()
+}
+
+fn take_queued_actions(mut queue: ResMut<ActionQueue>, mut actions: ResMut<StepActions>) {
+    actions.0 = mem::take(&mut queue.0);
+}
+
+/// Where the simulation put an entity at the end of the last two fixed steps.
+/// Its [`Transform`] is blended between them every frame. The simulation
+/// writes `translation` and `rotation`, never the [`Transform`] itself.
+#[derive(Component, Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Interpolated {
+    pub translation: Vec3,
+    /// Rotation around the z axis.
+    pub rotation: f32,
+    previous_translation: Vec3,
+    previous_rotation: f32,
+}
+
+impl Interpolated {
+    pub fn new(translation: Vec3, rotation: f32) -> Self {
+        Self {
+            translation,
+            rotation: rotation.rem_euclid(TAU),
+            previous_translation: translation,
+            previous_rotation: rotation.rem_euclid(TAU),
+        }
+    }
+
+    /// Blend from the previous to the current step by `fraction`.
+    fn blend(&self, fraction: f32) -> (Vec3, Quat) {
+        let previous_rotation = Quat::from_rotation_z(self.previous_rotation);
+        let rotation = Quat::from_rotation_z(self.rotation);
+        (
+            self.previous_translation.lerp(self.translation, fraction),
+            previous_rotation.slerp(rotation, fraction),
+        )
+    }
+}
+
+fn store_previous_state(mut query: Query<&mut Interpolated>) {
+    for mut interpolated in &mut query {
+        interpolated.previous_translation = interpolated.translation;
+        interpolated.previous_rotation = interpolated.rotation;
+    }
+}
+
+fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&Interpolated, &mut Transform)>,
+) {
+    let fraction = fixed_time.overstep_fraction();
+    for (interpolated, mut transform) in &mut query {
+        (transform.translation, transform.rotation) = interpolated.blend(fraction);
+    }
+}
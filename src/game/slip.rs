@@ -0,0 +1,97 @@
+//! The "slip" in Gear Slip: a jump that doesn't reach another gear sends the
+//! player flying off along the tangent of their orbit. They land on the first
+//! gear they fly into, or fall off the screen and respawn.
+
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        gear_jump::{jump_targets, trigger_landing, GearQuery},
+        mechanics::{
+            jump,
+            player::{has_fallen, FreeFlight, RevolutionMovement},
+        },
+        rotational_movement::{AngularVelocity, OwnSpeed},
+        simulation::Interpolated,
+        spawn::{gear::GoalGear, level::RespawnPlayer, player::Player},
+    },
+    SimSet,
+};
+
+/// The area levels are played in, centered on the origin. It's the size of
+/// the window when it isn't resized, but doesn't follow the window, so a run
+/// plays out the same at any window size.
+pub const PLAYFIELD_SIZE: Vec2 = Vec2::new(1280.0, 720.0);
+
+/// How far outside of the playfield the player has to be to count as fallen.
+const FALL_MARGIN: f32 = 64.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<FreeFlight>();
+    app.add_systems(FixedUpdate, apply_free_flight.in_set(SimSet::Move));
+    app.add_systems(
+        FixedUpdate,
+        (land_from_free_flight, detect_fall)
+            .chain()
+            .in_set(SimSet::Collide),
+    );
+}
+
+/// Triggered when the player flies off the screen without landing on a gear.
+#[derive(Event, Debug)]
+pub struct PlayerFell;
+
+fn apply_free_flight(
+    time: Res<Time>,
+    speed: Res<AngularVelocity>,
+    mut flight_query: Query<(
+        &FreeFlight,
+        &mut RevolutionMovement,
+        &mut Interpolated,
+        Option<&OwnSpeed>,
+    )>,
+) {
+    for (flight, mut movement, mut interpolated, own_speed) in &mut flight_query {
+        let speed = own_speed.map_or(speed.0, |own_speed| own_speed.0);
+        flight.advance(&mut movement, speed, time.delta_seconds());
+        interpolated.translation = movement.position;
+    }
+}
+
+fn land_from_free_flight(
+    mut commands: Commands,
+    gears: GearQuery,
+    goals: Query<(), With<GoalGear>>,
+    mut player: Query<(Entity, &mut RevolutionMovement), (With<Player>, With<FreeFlight>)>,
+) {
+    let Ok((entity, mut player_movement)) = player.get_single_mut() else {
+        return;
+    };
+
+    // The player is still inside of the gear they launched from at first, but
+    // `jump::catch` skips that one.
+    if let Some(to) = jump::catch(jump_targets(&gears), &mut player_movement) {
+        commands.entity(entity).remove::<FreeFlight>();
+        trigger_landing(&mut commands, &goals, to);
+    }
+}
+
+fn detect_fall(
+    mut commands: Commands,
+    player: Query<(&RevolutionMovement, &FreeFlight), With<Player>>,
+) {
+    let Ok((player_movement, flight)) = player.get_single() else {
+        return;
+    };
+
+    if has_fallen(fall_bounds(), player_movement, flight) {
+        commands.trigger(PlayerFell);
+        commands.trigger(RespawnPlayer);
+    }
+}
+
+/// The area the player has to stay in to not count as fallen.
+pub(super) fn fall_bounds() -> Rect {
+    let half_size = PLAYFIELD_SIZE / 2.0 + FALL_MARGIN;
+    Rect::from_corners(-half_size, half_size)
+}
@@ -0,0 +1,478 @@
+//! Work out how to beat a level.
+//! The solver plays the level at normal speed in fixed steps, using the same
+//! movement code as the game. Capture zones are round, so where the player can
+//! jump only depends on where they are on their orbit, not on how far the gears
+//! have turned. That makes the landing spot on a gear a complete description of
+//! the player's state, and the search only has to visit each one once. For
+//! the same reason, a spot on a gear the player already jumped from sooner, or
+//! with fewer jumps, isn't worth jumping from again.
+//! A jump that misses sends the player flying, and they can still land on a
+//! gear in their way, like in the game.
+
+use std::{cmp::Reverse, collections::BinaryHeap, f32::consts::TAU, mem};
+
+use bevy::prelude::*;
+
+use crate::game::{
+    level::Level,
+    mechanics::{
+        capture_zone::CaptureZone,
+        gear::RotationalMovement,
+        gear_train,
+        jump::{self, JumpOutcome, JumpTarget},
+        kinematics::angle_of,
+        player::{has_fallen, FreeFlight, RevolutionMovement},
+    },
+    simulation::SIMULATION_HZ,
+    slip::fall_bounds,
+};
+
+/// The time between two steps of the solver, in seconds. The same as the
+/// game's fixed steps.
+pub const SOLVER_TIMESTEP: f32 = (1.0 / SIMULATION_HZ) as f32;
+/// How finely landing spots are told apart. Spots closer than this are
+/// treated as the same spot.
+const ANGLE_BINS: f32 = 720.0;
+
+/// A gear as far as the solver is concerned.
+pub struct SolverGear {
+    pub position: Vec2,
+    pub movement: RotationalMovement,
+    pub capture_zone: CaptureZone,
+}
+
+impl SolverGear {
+    fn jump_target(&self) -> JumpTarget<'_> {
+        JumpTarget {
+            position: self.position,
+            movement: &self.movement,
+            capture_zone: &self.capture_zone,
+        }
+    }
+}
+
+/// A level to solve.
+pub struct Problem {
+    pub gears: Vec<SolverGear>,
+    /// The gear the player starts on.
+    pub start: usize,
+    pub player: RevolutionMovement,
+    pub goal: usize,
+}
+
+impl Problem {
+    /// The problem of beating `level`, which has to be valid.
+    pub fn from_level(level: &Level) -> Self {
+        let drives = gear_train::propagate(&level.train());
+        let gears = level
+            .gears
+            .iter()
+            .zip(&drives)
+            .map(|(gear, drive)| SolverGear {
+                position: gear.position,
+                movement: RotationalMovement::new(drive.direction(), gear.teeth, gear.initial_step),
+                capture_zone: CaptureZone::of_gear(gear.teeth),
+            })
+            .collect();
+
+        let start = &level.gears[level.player.gear];
+        Self {
+            gears,
+            start: level.player.gear,
+            player: RevolutionMovement::new(
+                start.position.extend(0.0),
+                drives[level.player.gear].direction(),
+                start.teeth,
+                level.player.initial_step,
+            ),
+            goal: level.goal,
+        }
+    }
+}
+
+/// What makes one solution better than another. Ties are broken by the other one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    FewestJumps,
+    EarliestCompletion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jump {
+    /// Seconds since the start of the level.
+    pub time: f32,
+    /// When the player lands on `to`. Later than `time` if the jump missed
+    /// and the player flew into `to`.
+    pub landing_time: f32,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// The jumps that take the player from the start to the goal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    pub jumps: Vec<Jump>,
+}
+
+impl Solution {
+    /// Seconds until the player lands on the goal.
+    pub fn completion_time(&self) -> f32 {
+        self.jumps.last().map_or(0.0, |jump| jump.landing_time)
+    }
+}
+
+struct Node {
+    gear: usize,
+    player: RevolutionMovement,
+    step: u64,
+    jumps: u64,
+    parent: Option<(usize, Jump)>,
+}
+
+/// The best way to get from the start to the goal, or `None` if the goal
+/// can't be reached.
+pub fn solve(problem: &Problem, objective: Objective) -> Option<Solution> {
+    let priority = |step: u64, jumps: u64| match objective {
+        Objective::FewestJumps => (jumps, step),
+        Objective::EarliestCompletion => (step, jumps),
+    };
+
+    let mut nodes = vec![Node {
+        gear: problem.start,
+        player: problem.player.clone(),
+        step: 0,
+        jumps: 0,
+        parent: None,
+    }];
+    let spots = problem.gears.len() * ANGLE_BINS as usize;
+    let mut best = vec![None; spots];
+    let mut expanded = vec![false; spots];
+    let mut launched = vec![None; spots];
+    let mut queue = BinaryHeap::from([Reverse((priority(0, 0), 0))]);
+
+    while let Some(Reverse((_, index))) = queue.pop() {
+        let node = &nodes[index];
+        if node.gear == problem.goal {
+            return Some(solution_to(&nodes, index));
+        }
+        if mem::replace(&mut expanded[spot(node.gear, &node.player)], true) {
+            continue;
+        }
+
+        let (from, step, jumps) = (node.gear, node.step, node.jumps + 1);
+        let launch_from = |wait: u64, player: &RevolutionMovement| {
+            let key = priority(step + wait, jumps - 1);
+            let launched = &mut launched[spot(from, player)];
+            // Slow gears pass through a spot in more than one step.
+            if let Some((best_key, by)) = *launched {
+                if best_key <= key {
+                    return by == index;
+                }
+            }
+            *launched = Some((key, index));
+            true
+        };
+        for (wait, flight, to, player) in jumps_from(problem, &node.player, launch_from) {
+            let jump_step = step + wait;
+            let step = jump_step + flight;
+            let key = priority(step, jumps);
+            let best = &mut best[spot(to, &player)];
+            if best.is_some_and(|best_key| best_key <= key) {
+                continue;
+            }
+            *best = Some(key);
+
+            let jump = Jump {
+                time: jump_step as f32 * SOLVER_TIMESTEP,
+                landing_time: step as f32 * SOLVER_TIMESTEP,
+                from,
+                to,
+            };
+            nodes.push(Node {
+                gear: to,
+                player,
+                step,
+                jumps,
+                parent: Some((index, jump)),
+            });
+            queue.push(Reverse((key, nodes.len() - 1)));
+        }
+    }
+
+    None
+}
+
+/// Whether the goal can be reached at all. Quicker than [`solve`], since it
+/// stops at the first way there it comes across.
+#[cfg(test)]
+pub fn is_solvable(problem: &Problem) -> bool {
+    if problem.start == problem.goal {
+        return true;
+    }
+    let spots = problem.gears.len() * ANGLE_BINS as usize;
+    let mut visited = vec![false; spots];
+    visited[spot(problem.start, &problem.player)] = true;
+    let mut launched = vec![None; spots];
+    let mut unexplored = vec![(problem.start, problem.player.clone())];
+    for index in 0.. {
+        let Some((from, player)) = unexplored.pop() else {
+            break;
+        };
+        let launch_from = |_, player: &RevolutionMovement| {
+            *launched[spot(from, player)].get_or_insert(index) == index
+        };
+        for (_, _, to, landed) in jumps_from(problem, &player, launch_from) {
+            if to == problem.goal {
+                return true;
+            }
+            if !mem::replace(&mut visited[spot(to, &landed)], true) {
+                unexplored.push((to, landed));
+            }
+        }
+    }
+    false
+}
+
+/// The gear the player is on and where on it they are, as an index into a
+/// table with [`ANGLE_BINS`] spots for each gear.
+fn spot(gear: usize, player: &RevolutionMovement) -> usize {
+    let angle = angle_of(player.position.xy(), player.anchor.xy()).rem_euclid(TAU);
+    let bin = ((angle / TAU * ANGLE_BINS) as usize).min(ANGLE_BINS as usize - 1);
+    gear * ANGLE_BINS as usize + bin
+}
+
+/// The gears to jump onto, by index.
+fn jump_targets(problem: &Problem) -> impl Iterator<Item = (usize, JumpTarget<'_>)> {
+    problem
+        .gears
+        .iter()
+        .map(SolverGear::jump_target)
+        .enumerate()
+}
+
+/// Every jump the player can make during one revolution around their gear,
+/// until `launch_from` turns down the spot they're at. It's given the number
+/// of steps so far. Each jump comes with the number of steps until it, the
+/// number of steps spent flying after it missed, the gear it lands on and
+/// where it leaves the player.
+fn jumps_from(
+    problem: &Problem,
+    player: &RevolutionMovement,
+    mut launch_from: impl FnMut(u64, &RevolutionMovement) -> bool,
+) -> Vec<(u64, u64, usize, RevolutionMovement)> {
+    let orbit = player.orbit();
+    // If the player doesn't go anywhere, they still have to be moved onto the orbit.
+    let steps = orbit
+        .period()
+        .map_or(1, |period| (period / SOLVER_TIMESTEP).ceil() as u64);
+    // The player can only land right away on gears their orbit passes through.
+    // The rest are skipped, but the order is kept, since the first gear in reach wins.
+    let targets: Vec<_> = jump_targets(problem)
+        .filter(|(_, target)| {
+            orbit
+                .time_until_inside(target.capture_zone, target.position)
+                .is_some()
+        })
+        .collect();
+
+    let mut player = player.clone();
+    let mut jumps = Vec::new();
+    for step in 1..=steps {
+        player.update_position(1.0, SOLVER_TIMESTEP);
+        // The rest of the way was passed before too, so it's no better.
+        if !launch_from(step, &player) {
+            break;
+        }
+        let mut landed = player.clone();
+        let landing = match jump::jump(targets.iter().copied(), &mut landed) {
+            JumpOutcome::Landed(to) => Some((0, to)),
+            JumpOutcome::Missed(flight) => fly(problem, &flight, &mut landed),
+        };
+        if let Some((flight, to)) = landing {
+            jumps.push((step, flight, to, landed));
+        }
+    }
+    jumps
+}
+
+/// Fly the `player` off after a missed jump until they land on a gear or fall.
+/// Returns the number of steps the flight took and the gear it ended on.
+fn fly(
+    problem: &Problem,
+    flight: &FreeFlight,
+    player: &mut RevolutionMovement,
+) -> Option<(u64, usize)> {
+    let start = player.position.xy();
+    let anchor = player.anchor.xy();
+    let entry = problem
+        .gears
+        .iter()
+        .filter(|gear| gear.position != anchor)
+        .filter_map(|gear| {
+            time_until_within(
+                start,
+                flight.velocity,
+                gear.position,
+                gear.capture_zone.0.outer_circle.radius,
+            )
+        })
+        .min_by(f32::total_cmp)?;
+
+    // Checking every step of a long flight is slow, so skip to just before the
+    // player can first land. The flight is straight and the bounds are a
+    // rectangle, so a player that fell on the way is still out of them after.
+    let skipped = ((entry / SOLVER_TIMESTEP) as u64).saturating_sub(1);
+    for _ in 0..skipped {
+        flight.advance(player, 1.0, SOLVER_TIMESTEP);
+    }
+    let bounds = fall_bounds();
+    if skipped > 0 && !bounds.contains(player.position.xy()) {
+        return None;
+    }
+    for step in skipped + 1.. {
+        flight.advance(player, 1.0, SOLVER_TIMESTEP);
+        if let Some(to) = jump::catch(jump_targets(problem), player) {
+            return Some((step, to));
+        }
+        if has_fallen(bounds, player, flight) {
+            return None;
+        }
+    }
+    unreachable!()
+}
+
+/// How long a point moving from `start` with `velocity` takes to come within
+/// `radius` of `center`, if it ever does.
+fn time_until_within(start: Vec2, velocity: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - center;
+    let c = offset.length_squared() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let a = velocity.length_squared();
+    let b = offset.dot(velocity);
+    let discriminant = b * b - a * c;
+    (b < 0.0 && discriminant >= 0.0).then(|| (-b - discriminant.sqrt()) / a)
+}
+
+fn solution_to(nodes: &[Node], mut index: usize) -> Solution {
+    let mut jumps = Vec::new();
+    while let Some((parent, jump)) = nodes[index].parent {
+        jumps.push(jump);
+        index = parent;
+    }
+    jumps.reverse();
+    Solution { jumps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        editor,
+        generator::{self, GeneratorSettings},
+    };
+
+    const HAND_AUTHORED_LEVELS: &[&str] =
+        &[include_str!("../../assets/levels/three_gears.level.ron")];
+
+    /// Play `solution` with the game's movement code and return the gear the
+    /// player ends up on.
+    fn replay(problem: &Problem, solution: &Solution) -> usize {
+        let mut gear = problem.start;
+        let mut player = problem.player.clone();
+        let mut step = 0;
+        for jump in &solution.jumps {
+            assert_eq!(jump.from, gear);
+            while step < (jump.time / SOLVER_TIMESTEP).round() as u64 {
+                player.update_position(1.0, SOLVER_TIMESTEP);
+                step += 1;
+            }
+            gear = match jump::jump(jump_targets(problem), &mut player) {
+                JumpOutcome::Landed(to) => to,
+                JumpOutcome::Missed(flight) => loop {
+                    flight.advance(&mut player, 1.0, SOLVER_TIMESTEP);
+                    step += 1;
+                    if let Some(to) = jump::catch(jump_targets(problem), &mut player) {
+                        break to;
+                    }
+                    assert!(!has_fallen(fall_bounds(), &player, &flight));
+                },
+            };
+            assert_eq!(gear, jump.to);
+            assert_eq!(step, (jump.landing_time / SOLVER_TIMESTEP).round() as u64);
+        }
+        gear
+    }
+
+    #[test]
+    fn hand_authored_levels_are_solvable() {
+        for source in HAND_AUTHORED_LEVELS {
+            let level: Level = ron::from_str(source).unwrap();
+            let problem = Problem::from_level(&level);
+            let solution = solve(&problem, Objective::FewestJumps);
+            assert!(solution.is_some(), "\"{}\" is unsolvable", level.name);
+            assert_eq!(replay(&problem, &solution.unwrap()), level.goal);
+        }
+    }
+
+    #[test]
+    fn three_gears_takes_one_jump() {
+        let level: Level = ron::from_str(HAND_AUTHORED_LEVELS[0]).unwrap();
+        let solution = solve(&Problem::from_level(&level), Objective::FewestJumps).unwrap();
+        assert_eq!(solution.jumps.len(), 1);
+        assert_eq!(solution.jumps[0].to, level.goal);
+    }
+
+    #[test]
+    fn generated_levels_are_solvable() {
+        for difficulty in 0..=5 {
+            let settings = GeneratorSettings::with_difficulty(difficulty);
+            for seed in 0..10 {
+                let level = generator::generate(seed, &settings).unwrap();
+                let problem = Problem::from_level(&level);
+
+                let fewest = solve(&problem, Objective::FewestJumps).unwrap();
+                let earliest = solve(&problem, Objective::EarliestCompletion).unwrap();
+                assert!(fewest.jumps.len() <= settings.jumps, "seed {seed}");
+                assert!(fewest.jumps.len() <= earliest.jumps.len(), "seed {seed}");
+                assert!(
+                    earliest.completion_time() <= fewest.completion_time(),
+                    "seed {seed}"
+                );
+                assert_eq!(replay(&problem, &fewest), level.goal);
+                assert_eq!(replay(&problem, &earliest), level.goal);
+            }
+        }
+    }
+
+    #[test]
+    fn missed_jumps_can_fly_onto_a_gear() {
+        // The second gear is out of reach from the orbit around the first,
+        // but in the way of a player flying off of it.
+        let mut level = editor::new_level();
+        level.goal = editor::add_gear(&mut level, Vec2::new(0.0, 300.0));
+        let problem = Problem::from_level(&level);
+
+        let solution = solve(&problem, Objective::FewestJumps).unwrap();
+        assert_eq!(solution.jumps.len(), 1);
+        assert!(solution.jumps[0].landing_time > solution.jumps[0].time);
+        assert_eq!(replay(&problem, &solution), level.goal);
+    }
+
+    #[test]
+    fn starting_on_the_goal_takes_no_jumps() {
+        let mut level = generator::generate(0, &GeneratorSettings::default()).unwrap();
+        level.goal = level.player.gear;
+        let solution = solve(&Problem::from_level(&level), Objective::FewestJumps).unwrap();
+        assert!(solution.jumps.is_empty());
+        assert_eq!(solution.completion_time(), 0.0);
+    }
+
+    #[test]
+    fn standing_still_is_unsolvable() {
+        let mut level = generator::generate(0, &GeneratorSettings::default()).unwrap();
+        level.gears[0].motor = None;
+        assert!(solve(&Problem::from_level(&level), Objective::EarliestCompletion).is_none());
+    }
+}
@@ -5,64 +5,90 @@ use bevy::prelude::*;
 use crate::{
     game::{
         assets::{HandleMap, ImageKey},
-        rotational_movement::{RotationDirection, RotationalMovement},
+        mechanics::{
+            capture_zone::CaptureZone,
+            gear::{gear_radius, RotationalMovement, BASE_GEAR_RADIUS},
+            gear_train::GearDrive,
+        },
+        simulation::Interpolated,
     },
     screen::Screen,
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_gear);
-    app.register_type::<Gear>();
+    app.register_type::<(Gear, GoalGear, Jammed, CaptureZone)>();
 }
 
+/// Jammed gears are tinted towards this color.
+pub const JAMMED_TINT: Color = Color::srgb(0.35, 0.1, 0.1);
+
 #[derive(Event, Debug)]
 pub struct SpawnGear {
     pub position: Vec2,
     pub initial_gear_step: bool,
-    pub direction: RotationDirection,
+    pub drive: GearDrive,
+    pub teeth: u32,
     pub color: Color,
+    /// Whether reaching this gear completes the level.
+    pub goal: bool,
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Gear;
 
-#[derive(Component, Debug, Clone, Copy, Default, Reflect)]
+/// Marker for the gear the player has to reach to complete the level.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
+#[reflect(Component)]
+pub struct GoalGear;
+
+/// Marker for gears that can't turn because their gear train is jammed.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
-pub struct GearBoundingBox(pub Rect);
+pub struct Jammed;
 
 fn spawn_gear(
     trigger: Trigger<SpawnGear>,
     mut commands: Commands,
     image_handles: Res<HandleMap<ImageKey>>,
-    assets: Res<Assets<Image>>,
 ) {
     let gear = trigger.event();
-    let rotational_movement = RotationalMovement::new(gear.direction, gear.initial_gear_step);
-    let transform = Transform::from_translation(gear.position.extend(1.0)).with_rotation(
-        Quat::from_rotation_z(rotational_movement.get_initial_rotation()),
-    );
+    let rotational_movement =
+        RotationalMovement::new(gear.drive.direction(), gear.teeth, gear.initial_gear_step);
+    let interpolated = Interpolated::new(gear.position.extend(1.0), rotational_movement.rotation());
+    let transform = Transform::from_translation(interpolated.translation)
+        .with_rotation(Quat::from_rotation_z(interpolated.rotation))
+        // `gear.png` is drawn at the base size.
+        .with_scale(Vec2::splat(gear_radius(gear.teeth) / BASE_GEAR_RADIUS).extend(1.0));
 
     let texture = image_handles[&ImageKey::Gear].clone_weak();
-    let image_dimensions = assets.get(&texture).unwrap().size();
-    let scaled_image_dimension = image_dimensions.as_vec2() * transform.scale.truncate();
-    let bounding_box =
-        Rect::from_center_size(transform.translation.truncate(), scaled_image_dimension);
 
-    commands.spawn((
+    let mut entity = commands.spawn((
         Name::new("Gear"),
         Gear,
         SpriteBundle {
             texture,
             transform,
             sprite: Sprite {
-                color: gear.color,
+                color: if gear.drive == GearDrive::Jammed {
+                    gear.color.mix(&JAMMED_TINT, 0.6)
+                } else {
+                    gear.color
+                },
                 ..Default::default()
             },
             ..Default::default()
         },
-        GearBoundingBox(bounding_box),
+        CaptureZone::of_gear(gear.teeth),
         rotational_movement,
+        interpolated,
         StateScoped(Screen::Playing),
     ));
+    if gear.goal {
+        entity.insert(GoalGear);
+    }
+    if gear.drive == GearDrive::Jammed {
+        entity.insert(Jammed);
+    }
 }
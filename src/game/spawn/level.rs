@@ -1,48 +1,86 @@
-//! Spawn the main level by triggering other observers.
+//! Spawn a level by triggering other observers.
 
 use bevy::prelude::*;
 
 use crate::game::{
-    rotational_movement::RotationDirection,
-    spawn::{gear::SpawnGear, player::SpawnPlayer},
+    level::Level,
+    mechanics::gear_train,
+    simulation::SimulationTick,
+    spawn::{
+        gear::SpawnGear,
+        player::{Player, SpawnPlayer},
+    },
 };
 
 pub(super) fn plugin(app: &mut App) {
     app.observe(spawn_level);
+    app.observe(respawn_player);
 }
 
+/// Trigger this event to spawn the given level.
 #[derive(Event, Debug)]
-pub struct SpawnLevel;
-
-fn spawn_level(_trigger: Trigger<SpawnLevel>, mut commands: Commands) {
-    // The only thing we have in our level is a player,
-    // but add things like walls etc. here.
-    let first_position = Vec2::new(0.0, (100.0 / f32::sqrt(2.0)) + 2.0);
-
-    // let's start with two gears
-    commands.trigger(SpawnGear {
-        position: first_position,
-        initial_gear_step: false,
-        direction: RotationDirection::Clockwise,
-        color: Color::srgba(0.412, 1.000, 0.917, 1.000),
-    });
-    commands.trigger(SpawnGear {
-        position: Vec2::new(108.0, -100.0 / f32::sqrt(2.0)),
-        initial_gear_step: false,
-        direction: RotationDirection::CounterClockwise,
-        color: Color::srgba(0.919, 0.971, 0.463, 1.000),
-    });
-    commands.trigger(SpawnGear {
-        position: Vec2::new(-108.0, -100.0 / f32::sqrt(2.0)),
-        initial_gear_step: false,
-        direction: RotationDirection::CounterClockwise,
-        color: Color::srgba(1.000, 0.625, 0.625, 1.000),
-    });
-
-    commands.trigger(SpawnPlayer {
-        position: first_position,
-        revolution_radius: 92.0,
-        initial_gear_step: true,
-        rotation_direction: RotationDirection::Clockwise,
-    });
+pub struct SpawnLevel(pub Handle<Level>);
+
+/// Trigger this event to put the player back at the start of the current level.
+#[derive(Event, Debug)]
+pub struct RespawnPlayer;
+
+/// The level that was spawned last.
+#[derive(Resource, Debug)]
+pub struct CurrentLevel(pub Handle<Level>);
+
+fn spawn_level(trigger: Trigger<SpawnLevel>, mut commands: Commands, levels: Res<Assets<Level>>) {
+    let handle = &trigger.event().0;
+    let Some(level) = levels.get(handle) else {
+        // The asset server has already logged why the level failed to load.
+        error!("Cannot spawn level {:?}: it is not loaded", handle);
+        return;
+    };
+    info!("Spawning level \"{}\"", level.name);
+
+    let drives = gear_train::propagate(&level.train());
+    for (index, gear) in level.gears.iter().enumerate() {
+        commands.trigger(SpawnGear {
+            position: gear.position,
+            initial_gear_step: gear.initial_step,
+            drive: drives[index],
+            teeth: gear.teeth,
+            color: gear.color.into(),
+            goal: index == level.goal,
+        });
+    }
+
+    commands.trigger(player_start(level));
+    commands.insert_resource(CurrentLevel(handle.clone()));
+    commands.insert_resource(SimulationTick::default());
+}
+
+fn respawn_player(
+    _trigger: Trigger<RespawnPlayer>,
+    mut commands: Commands,
+    current_level: Option<Res<CurrentLevel>>,
+    levels: Res<Assets<Level>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    let Some(level) = current_level.and_then(|current| levels.get(&current.0)) else {
+        return;
+    };
+
+    for entity in &player_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.trigger(player_start(level));
+}
+
+/// How the player starts out on `level`.
+pub fn player_start(level: &Level) -> SpawnPlayer {
+    // `Level::validate` guarantees that the player's gear exists.
+    let start_gear = &level.gears[level.player.gear];
+    let drives = gear_train::propagate(&level.train());
+    SpawnPlayer {
+        position: start_gear.position,
+        gear_teeth: start_gear.teeth,
+        initial_gear_step: level.player.initial_step,
+        rotation_direction: drives[level.player.gear].direction(),
+    }
 }
@@ -6,7 +6,8 @@ use crate::{
     game::{
         animation::PlayerAnimation,
         assets::{HandleMap, ImageKey},
-        rotational_movement::{RevolutionMovement, RotationDirection},
+        mechanics::{gear::RotationDirection, player::RevolutionMovement},
+        simulation::Interpolated,
     },
     screen::Screen,
 };
@@ -19,15 +20,36 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Event, Debug)]
 pub struct SpawnPlayer {
     pub position: Vec2,
-    pub revolution_radius: f32,
+    /// Size of the gear the player starts on.
+    pub gear_teeth: u32,
     pub initial_gear_step: bool,
-    pub rotation_direction: RotationDirection,
+    pub rotation_direction: Option<RotationDirection>,
+}
+
+impl SpawnPlayer {
+    /// The player's movement at the start, drawn at depth `z`.
+    pub fn movement(&self, z: f32) -> RevolutionMovement {
+        RevolutionMovement::new(
+            self.position.extend(z),
+            self.rotation_direction,
+            self.gear_teeth,
+            self.initial_gear_step,
+        )
+    }
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
 pub struct Player;
 
+/// The player is drawn in front of the gears.
+const PLAYER_Z: f32 = 5.0;
+
+/// The grid of animation frames in the Sootboi image.
+pub fn sootboi_layout() -> TextureAtlasLayout {
+    TextureAtlasLayout::from_grid(UVec2::splat(300), 6, 2, Some(UVec2::splat(1)), None)
+}
+
 fn spawn_player(
     trigger: Trigger<SpawnPlayer>,
     mut commands: Commands,
@@ -38,13 +60,11 @@ fn spawn_player(
     // By attaching it to a [`SpriteBundle`] and providing an index, we can specify which section of the image we want to see.
     // We will use this to animate our player character. You can learn more about texture atlases in this example:
     // https://github.com/bevyengine/bevy/blob/latest/examples/2d/texture_atlas.rs
-    let layout =
-        TextureAtlasLayout::from_grid(UVec2::splat(300), 6, 2, Some(UVec2::splat(1)), None);
-    let texture_atlas_layout = texture_atlas_layouts.add(layout);
+    let texture_atlas_layout = texture_atlas_layouts.add(sootboi_layout());
     let player_animation = PlayerAnimation::new();
 
     let spawn_params = trigger.event();
-    let starting_position = spawn_params.position.extend(5.0);
+    let movement = spawn_params.movement(PLAYER_Z);
 
     commands.spawn((
         Name::new("Player"),
@@ -52,7 +72,7 @@ fn spawn_player(
         SpriteBundle {
             texture: image_handles[&ImageKey::Sootboi].clone_weak(),
             transform: Transform::from_scale(Vec2::splat(0.2).extend(1.0))
-                .with_translation(starting_position),
+                .with_translation(movement.position),
             ..Default::default()
         },
         TextureAtlas {
@@ -63,12 +83,8 @@ fn spawn_player(
         // Movement { speed: 420.0 },
         // ConstrainWithinWindow,
         player_animation,
-        RevolutionMovement::new(
-            starting_position,
-            spawn_params.rotation_direction,
-            spawn_params.revolution_radius,
-            spawn_params.initial_gear_step,
-        ),
+        Interpolated::new(movement.position, 0.0),
+        movement,
         StateScoped(Screen::Playing),
     ));
 }
@@ -0,0 +1,253 @@
+//! Run the game without a window, audio or a GPU, to check gameplay in tests.
+//! Levels are spawned directly instead of loaded from files, and every
+//! [`App::update`] advances the simulation by exactly one fixed step.
+
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    configure_sets,
+    game::{
+        self,
+        assets::{HandleMap, ImageKey},
+        gear_jump::{JumpMissed, LevelCompleted, PlayerJumped, PlayerLanded},
+        mechanics::{
+            gear::RotationalMovement,
+            player::{FreeFlight, RevolutionMovement},
+        },
+        rotational_movement::SpeedChanged,
+        simulation::{ActionQueue, SimulationTick, SIMULATION_HZ},
+        slip::PlayerFell,
+        spawn::{
+            gear::{Gear, GoalGear, Jammed},
+            level::SpawnLevel,
+            player::Player,
+        },
+    },
+};
+
+pub use crate::game::{level::Level, simulation::PlayerAction};
+
+/// Gives up on a step that doesn't happen after this many frames.
+const MAX_FRAMES_PER_STEP: usize = 8;
+
+/// The gameplay plugins on top of [`MinimalPlugins`].
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        configure_sets(app);
+
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), InputPlugin));
+        // Every frame takes exactly as long as one fixed step.
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / SIMULATION_HZ,
+        )));
+
+        // Sprites are spawned without ever being drawn, so they don't need their images.
+        app.init_asset::<Image>();
+        app.init_asset::<TextureAtlasLayout>();
+        app.insert_resource(HandleMap::<ImageKey>::from([
+            (ImageKey::Ducky, Handle::default()),
+            (ImageKey::Gear, Handle::default()),
+            (ImageKey::Sootboi, Handle::default()),
+        ]));
+        app.init_asset::<Level>();
+
+        app.add_plugins(game::gameplay_plugin);
+
+        app.init_resource::<EventLog>();
+        app.observe(
+            |trigger: Trigger<PlayerJumped>, mut log: ResMut<EventLog>| {
+                let PlayerJumped { from } = *trigger.event();
+                log.0.push(GameplayEvent::Jumped { from });
+            },
+        );
+        app.observe(
+            |trigger: Trigger<PlayerLanded>, mut log: ResMut<EventLog>| {
+                let PlayerLanded { to } = *trigger.event();
+                log.0.push(GameplayEvent::Landed { to });
+            },
+        );
+        app.observe(|_: Trigger<JumpMissed>, mut log: ResMut<EventLog>| {
+            log.0.push(GameplayEvent::Missed);
+        });
+        app.observe(
+            |trigger: Trigger<SpeedChanged>, mut log: ResMut<EventLog>| {
+                let SpeedChanged { from, to } = *trigger.event();
+                log.0.push(GameplayEvent::SpeedChanged { from, to });
+            },
+        );
+        app.observe(|_: Trigger<LevelCompleted>, mut log: ResMut<EventLog>| {
+            log.0.push(GameplayEvent::LevelCompleted);
+        });
+        app.observe(|_: Trigger<PlayerFell>, mut log: ResMut<EventLog>| {
+            log.0.push(GameplayEvent::Fell);
+        });
+    }
+}
+
+/// Something that happened during play. Gears are told apart by `G`, which
+/// is their index in [`Harness::gears`] outside of the app.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameplayEvent<G = usize> {
+    Jumped { from: G },
+    Landed { to: G },
+    Missed,
+    SpeedChanged { from: f32, to: f32 },
+    LevelCompleted,
+    Fell,
+}
+
+impl<G> GameplayEvent<G> {
+    fn map_gears<H>(self, f: impl Fn(G) -> H) -> GameplayEvent<H> {
+        match self {
+            Self::Jumped { from } => GameplayEvent::Jumped { from: f(from) },
+            Self::Landed { to } => GameplayEvent::Landed { to: f(to) },
+            Self::Missed => GameplayEvent::Missed,
+            Self::SpeedChanged { from, to } => GameplayEvent::SpeedChanged { from, to },
+            Self::LevelCompleted => GameplayEvent::LevelCompleted,
+            Self::Fell => GameplayEvent::Fell,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct EventLog(Vec<GameplayEvent<Entity>>);
+
+/// Where the player is in the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerState {
+    pub position: Vec2,
+    /// The center of the gear the player is on, or last jumped off.
+    pub anchor: Vec2,
+    /// Whether the player missed a jump and is flying off.
+    pub flying: bool,
+}
+
+/// Where a gear is in the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GearState {
+    pub position: Vec2,
+    /// Rotation around the z axis.
+    pub rotation: f32,
+    pub goal: bool,
+    pub jammed: bool,
+}
+
+/// A headless app playing a single level.
+pub struct Harness {
+    app: App,
+}
+
+impl Harness {
+    /// Spawn `level`, ready for its first step.
+    pub fn new(level: Level) -> Self {
+        let mut app = App::new();
+        app.add_plugins(HeadlessPlugin);
+        app.finish();
+        app.cleanup();
+
+        let handle = app.world_mut().resource_mut::<Assets<Level>>().add(level);
+        app.world_mut().trigger(SpawnLevel(handle));
+        app.world_mut().flush();
+        Self { app }
+    }
+
+    /// Run one fixed step, taking `actions` in it.
+    pub fn step(&mut self, actions: &[PlayerAction]) {
+        self.app
+            .world_mut()
+            .resource_mut::<ActionQueue>()
+            .0
+            .extend_from_slice(actions);
+
+        let target = self.tick() + 1;
+        for _ in 0..MAX_FRAMES_PER_STEP {
+            self.app.update();
+            if self.tick() >= target {
+                return;
+            }
+        }
+        panic!("the simulation did not advance to step {target}");
+    }
+
+    /// Run `steps` fixed steps without any input.
+    pub fn run(&mut self, steps: u64) {
+        for _ in 0..steps {
+            self.step(&[]);
+        }
+    }
+
+    /// The number of the last step that ran, counting from 1.
+    pub fn tick(&self) -> u64 {
+        self.app.world().resource::<SimulationTick>().0
+    }
+
+    pub fn player(&mut self) -> PlayerState {
+        let (movement, flying) = self
+            .app
+            .world_mut()
+            .query_filtered::<(&RevolutionMovement, Has<FreeFlight>), With<Player>>()
+            .single(self.app.world());
+        PlayerState {
+            position: movement.position.xy(),
+            anchor: movement.anchor.xy(),
+            flying,
+        }
+    }
+
+    /// The gears, in the order they were spawned in.
+    pub fn gears(&mut self) -> Vec<GearState> {
+        self.gears_by_entity()
+            .into_iter()
+            .map(|(_, gear)| gear)
+            .collect()
+    }
+
+    fn gears_by_entity(&mut self) -> Vec<(Entity, GearState)> {
+        let mut gears: Vec<_> = self
+            .app
+            .world_mut()
+            .query_filtered::<(
+                Entity,
+                &Transform,
+                &RotationalMovement,
+                Has<GoalGear>,
+                Has<Jammed>,
+            ), With<Gear>>()
+            .iter(self.app.world())
+            .map(|(entity, transform, movement, goal, jammed)| {
+                (
+                    entity,
+                    GearState {
+                        position: transform.translation.xy(),
+                        rotation: movement.rotation(),
+                        goal,
+                        jammed,
+                    },
+                )
+            })
+            .collect();
+        gears.sort_by_key(|(entity, _)| *entity);
+        gears
+    }
+
+    /// Everything that happened so far, in order.
+    pub fn events(&mut self) -> Vec<GameplayEvent> {
+        let entities: Vec<_> = self
+            .gears_by_entity()
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect();
+        let index_of = |gear| entities.iter().position(|entity| *entity == gear).unwrap();
+        self.app
+            .world()
+            .resource::<EventLog>()
+            .0
+            .iter()
+            .map(|event| event.map_gears(index_of))
+            .collect()
+    }
+}
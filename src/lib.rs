@@ -1,24 +1,18 @@
 #[cfg(feature = "dev")]
 mod dev_tools;
 mod game;
+#[cfg(any(test, feature = "headless"))]
+pub mod headless;
 mod screen;
 mod ui;
 
-use bevy::{
-    asset::AssetMetaCheck,
-    audio::{AudioPlugin, Volume},
-    prelude::*,
-};
+use bevy::{asset::AssetMetaCheck, prelude::*};
 
 pub struct AppPlugin;
 
 impl Plugin for AppPlugin {
     fn build(&self, app: &mut App) {
-        // Order new `AppStep` variants by adding them here:
-        app.configure_sets(
-            Update,
-            (AppSet::TickTimers, AppSet::RecordInput, AppSet::Update).chain(),
-        );
+        configure_sets(app);
 
         // Spawn the main camera.
         app.add_systems(Startup, spawn_camera);
@@ -43,12 +37,6 @@ impl Plugin for AppPlugin {
                     }
                     .into(),
                     ..default()
-                })
-                .set(AudioPlugin {
-                    global_volume: GlobalVolume {
-                        volume: Volume::new(0.3),
-                    },
-                    ..default()
                 }),
         );
 
@@ -61,6 +49,26 @@ impl Plugin for AppPlugin {
     }
 }
 
+fn configure_sets(app: &mut App) {
+    // Order new `AppStep` variants by adding them here:
+    app.configure_sets(
+        Update,
+        (AppSet::TickTimers, AppSet::RecordInput, AppSet::Update).chain(),
+    );
+    // Order new `SimSet` variants by adding them here:
+    app.configure_sets(
+        FixedUpdate,
+        (
+            SimSet::Input,
+            SimSet::Replay,
+            SimSet::Act,
+            SimSet::Move,
+            SimSet::Collide,
+        )
+            .chain(),
+    );
+}
+
 /// High-level groupings of systems for the app in the `Update` schedule.
 /// When adding a new variant, make sure to order it in the `configure_sets`
 /// call above.
@@ -74,6 +82,23 @@ enum AppSet {
     Update,
 }
 
+/// High-level groupings of systems for the simulation in the `FixedUpdate`
+/// schedule. When adding a new variant, make sure to order it in the
+/// `configure_sets` function above.
+#[derive(SystemSet, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+enum SimSet {
+    /// Take the player input recorded since the last step.
+    Input,
+    /// Record or play back the step's input.
+    Replay,
+    /// Respond to player input.
+    Act,
+    /// Move gears and the player.
+    Move,
+    /// Respond to where things ended up.
+    Collide,
+}
+
 fn spawn_camera(mut commands: Commands) {
     commands.spawn((
         Name::new("Camera"),
@@ -0,0 +1,218 @@
+//! A screen to change what each action is bound to, reached from the settings
+//! screen. Changes are applied and saved right away.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::Screen;
+use crate::{
+    game::actions::{ActionMap, Binding, Buttons, InputAction},
+    ui::{focus::Focus, prelude::*},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ControlsState>();
+    app.add_systems(OnEnter(Screen::Controls), enter_controls);
+    app.add_systems(OnExit(Screen::Controls), exit_controls);
+
+    app.register_type::<ControlsAction>();
+    app.add_systems(
+        Update,
+        (capture_binding, handle_controls_action, show_bindings)
+            .chain()
+            .run_if(in_state(Screen::Controls)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum ControlsAction {
+    /// Wait for a button to add to the action's bindings.
+    Bind(InputAction),
+    Unbind(InputAction),
+    Reset,
+    Back,
+}
+
+#[derive(Resource, Debug, Default)]
+struct ControlsState {
+    /// The action waiting for a button to be bound to it.
+    binding: Option<InputAction>,
+    /// The last thing worth telling the player.
+    status: String,
+}
+
+#[derive(Component, Debug)]
+struct StatusText;
+
+/// The key that stops waiting for a button to bind, so it can't be bound this way.
+const CANCEL_KEY: KeyCode = KeyCode::Escape;
+
+fn enter_controls(mut commands: Commands, mut state: ResMut<ControlsState>) {
+    *state = default();
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Controls))
+        .with_children(|children| {
+            children.header("Controls");
+            children
+                .spawn((
+                    Name::new("Bindings"),
+                    NodeBundle {
+                        style: Style {
+                            display: Display::Grid,
+                            grid_template_columns: RepeatedGridTrack::auto(6),
+                            align_items: AlignItems::Center,
+                            column_gap: Px(10.0),
+                            row_gap: Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                ))
+                .with_children(|children| {
+                    for action in InputAction::ALL {
+                        children.spawn((
+                            Name::new("Action Name"),
+                            TextBundle::from_section(
+                                action.name(),
+                                TextStyle {
+                                    font_size: 20.0,
+                                    color: ui_palette::LABEL_TEXT,
+                                    ..default()
+                                },
+                            ),
+                        ));
+                        children
+                            .small_button("", 260.0)
+                            .insert(ControlsAction::Bind(action));
+                        children
+                            .small_button("Clear", 80.0)
+                            .insert(ControlsAction::Unbind(action));
+                    }
+                });
+            children.label("").insert(StatusText);
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|children| {
+                    children.button("Reset").insert(ControlsAction::Reset);
+                    children.button("Back").insert(ControlsAction::Back);
+                });
+        });
+}
+
+fn exit_controls(mut state: ResMut<ControlsState>, mut focus: ResMut<Focus>) {
+    state.binding = None;
+    focus.locked = false;
+}
+
+/// Bind the first button pressed while waiting for one. This runs before the
+/// buttons are handled, so the press that started waiting isn't bound.
+fn capture_binding(
+    buttons: Buttons,
+    mut action_map: ResMut<ActionMap>,
+    mut state: ResMut<ControlsState>,
+    mut focus: ResMut<Focus>,
+) {
+    let Some(action) = state.binding else {
+        return;
+    };
+    let Some(binding) = buttons.any_just_pressed() else {
+        return;
+    };
+    state.binding = None;
+    focus.locked = false;
+    if binding == Binding::Key(CANCEL_KEY) {
+        state.status.clear();
+        return;
+    }
+    state.status = match action_map.bind(action, binding) {
+        Ok(()) => format!("{} is bound to {binding}", action.name()),
+        Err(conflict) => format!("Cannot bind: {conflict}"),
+    };
+}
+
+fn handle_controls_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut action_map: ResMut<ActionMap>,
+    mut state: ResMut<ControlsState>,
+    mut focus: ResMut<Focus>,
+    mut button_query: InteractionQuery<&ControlsAction>,
+) {
+    if state.binding.is_some() {
+        return;
+    }
+    for (interaction, action) in &mut button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match *action {
+            ControlsAction::Bind(action) => {
+                state.binding = Some(action);
+                state.status = format!(
+                    "Press a button for {}, or {CANCEL_KEY:?} to cancel",
+                    action.name()
+                );
+                focus.locked = true;
+            }
+            ControlsAction::Unbind(action) => {
+                action_map.unbind_all(action);
+                state.status.clear();
+            }
+            ControlsAction::Reset => {
+                *action_map = default();
+                state.status.clear();
+            }
+            ControlsAction::Back => next_screen.set(Screen::Settings),
+        }
+    }
+}
+
+/// Show the bindings and the status when they change, along with any
+/// conflicting bindings.
+fn show_bindings(
+    action_map: Res<ActionMap>,
+    state: Res<ControlsState>,
+    button_query: Query<(&ControlsAction, &Children)>,
+    status_query: Query<&Children, With<StatusText>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !action_map.is_changed() && !state.is_changed() {
+        return;
+    }
+    for (action, children) in &button_query {
+        let ControlsAction::Bind(action) = *action else {
+            continue;
+        };
+        let text = if state.binding == Some(action) {
+            "...".to_string()
+        } else {
+            action_map.describe(action)
+        };
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut button_text) = texts.fetch_next() {
+            button_text.sections[0].value.clone_from(&text);
+        }
+    }
+
+    let mut status = state.status.clone();
+    for (action, conflict) in action_map.conflicts() {
+        status.push_str(&format!(
+            "\nWarning: {} and {} are both bound to {}",
+            conflict.action.name(),
+            action.name(),
+            conflict.binding
+        ));
+    }
+    for children in &status_query {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut status_text) = texts.fetch_next() {
+            status_text.sections[0].value.clone_from(&status);
+        }
+    }
+}
@@ -0,0 +1,643 @@
+//! The level editor. Designers lay out gears with the mouse and keyboard,
+//! play-test the level right away and save it as a level file.
+
+use std::{f32::consts::TAU, mem};
+
+use bevy::{
+    input::mouse::MouseWheel,
+    prelude::*,
+    tasks::{block_on, futures_lite::future, AsyncComputeTaskPool, Task},
+    window::PrimaryWindow,
+};
+
+use super::{playing::SelectedLevel, Screen};
+#[cfg(not(target_family = "wasm"))]
+use crate::game::save::storage;
+use crate::{
+    game::{
+        actions::{action_just_pressed, ActionMap, ActionState, InputAction},
+        assets::{HandleMap, ImageKey},
+        editor::{
+            self,
+            history::{Edit, History},
+            PlacementWarning,
+        },
+        level::Level,
+        mechanics::{
+            gear::{gear_radius, RotationalMovement, BASE_GEAR_RADIUS},
+            gear_train::{self, GearDrive},
+        },
+        settings::reduced_motion,
+        solver::{self, Objective, Problem},
+        spawn::gear::JAMMED_TINT,
+    },
+    ui::{controls::TextInput, focus::Focus, prelude::*},
+    AppSet,
+};
+
+/// Saved levels go in this directory in the game's data directory. They can
+/// be opened again by dropping them onto the window.
+#[cfg(not(target_family = "wasm"))]
+const LEVELS_DIRECTORY: &str = "levels";
+
+const MAX_NAME_LENGTH: usize = 32;
+
+const SELECTION_COLOR: Color = Color::WHITE;
+const START_COLOR: Color = Color::srgb(0.3, 0.9, 0.4);
+const GOAL_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const SNAP_COLOR: Color = Color::srgb(0.4, 0.7, 1.0);
+const OVERLAP_COLOR: Color = Color::srgb(1.0, 0.2, 0.2);
+const TOO_FAR_COLOR: Color = Color::srgb(1.0, 0.55, 0.1);
+
+const MOUSE_HELP: &str = "Click: add or select a gear, drag: move it, scroll: resize it\n\
+    Gears snap to mesh with the closest gear, Shift: snap the angle too, Alt: don't snap";
+
+/// The edits made on the selected gear by pressing their action.
+const GEAR_EDITS: [(InputAction, fn(usize) -> Edit); 5] = [
+    (InputAction::EditMotor, Edit::CycleMotor),
+    (InputAction::EditColor, Edit::CycleColor),
+    (InputAction::EditTeeth, Edit::ToggleInitialStep),
+    (InputAction::EditStart, Edit::SetStart),
+    (InputAction::EditGoal, Edit::SetGoal),
+];
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<EditorState>();
+    app.init_resource::<EditorHistory>();
+    app.init_resource::<SolverTask>();
+    app.add_systems(OnEnter(Screen::Editor), enter_editor);
+    app.add_systems(OnExit(Screen::Editor), exit_editor);
+    app.add_systems(
+        Update,
+        (
+            pick_and_drag,
+            resize_with_scroll,
+            (
+                edit_selected_gear,
+                undo.run_if(shortcut_pressed(InputAction::Undo)),
+                redo.run_if(shortcut_pressed(InputAction::Redo)),
+                play_test.run_if(action_just_pressed(InputAction::PlayTest)),
+                return_to_title_screen.run_if(action_just_pressed(InputAction::MenuBack)),
+            )
+                .chain()
+                .run_if(not(text_input_focused)),
+        )
+            .chain()
+            .in_set(AppSet::RecordInput)
+            .run_if(in_state(Screen::Editor)),
+    );
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(
+        Update,
+        (
+            save_level
+                .run_if(shortcut_pressed(InputAction::SaveLevel).and_then(not(text_input_focused))),
+            load_dropped_level,
+        )
+            .in_set(AppSet::RecordInput)
+            .run_if(in_state(Screen::Editor)),
+    );
+    app.add_systems(
+        Update,
+        (
+            sync_editor_gears,
+            show_level_name,
+            spin_editor_gears.run_if(not(reduced_motion)),
+            draw_markers,
+            solve_level.run_if(resource_changed::<EditorHistory>),
+            show_par,
+            show_status,
+        )
+            .chain()
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Editor)),
+    );
+}
+
+/// The level being edited. It's kept while play-testing.
+#[derive(Resource, Debug)]
+struct EditorLevel(Level);
+
+/// The edits made to the level, to undo them. It's kept while play-testing.
+#[derive(Resource, Debug, Default)]
+struct EditorHistory(History);
+
+#[derive(Resource, Debug, Default)]
+struct EditorState {
+    selected: Option<usize>,
+    /// Offset from the cursor to the center of the gear being dragged.
+    drag_offset: Option<Vec2>,
+    /// Where the dragged gear was before the drag, to undo the whole drag at once.
+    drag_start: Option<Vec2>,
+    /// The gear the dragged gear was snapped to mesh with.
+    snapped_to: Option<usize>,
+    /// The last thing worth telling the designer.
+    status: String,
+    /// How well the level can be played, as found by the solver.
+    par: String,
+}
+
+/// The solver working out the par of the level in the background.
+#[derive(Resource, Default)]
+struct SolverTask(Option<Task<String>>);
+
+/// Insert this while playing a level from the editor, to return to it afterwards.
+#[derive(Resource, Debug)]
+pub struct PlayTest;
+
+/// The sprite of a gear in the edited level.
+#[derive(Component, Debug)]
+struct EditorGear {
+    /// The index of the gear in the level.
+    index: usize,
+    movement: RotationalMovement,
+}
+
+#[derive(Component, Debug)]
+struct StatusText;
+
+/// The text input for the name of the level.
+#[derive(Component, Debug)]
+struct NameInput;
+
+/// The keys of the editor, from the current bindings.
+fn help(action_map: &ActionMap) -> String {
+    let describe = |action| action_map.describe(action);
+    let mut help = format!("{MOUSE_HELP}\n");
+    for (action, _) in GEAR_EDITS {
+        help.push_str(&format!(
+            "{}: {}, ",
+            describe(action),
+            action.name().to_lowercase()
+        ));
+    }
+    help.push_str(&format!(
+        "{}: remove\nCtrl+{}: undo, Ctrl+{}: redo, {}: play-test, Ctrl+{}: save, {}: title screen",
+        describe(InputAction::RemoveGear),
+        describe(InputAction::Undo),
+        describe(InputAction::Redo),
+        describe(InputAction::PlayTest),
+        describe(InputAction::SaveLevel),
+        describe(InputAction::MenuBack),
+    ));
+    help
+}
+
+fn enter_editor(
+    mut commands: Commands,
+    editor_level: Option<Res<EditorLevel>>,
+    action_map: Res<ActionMap>,
+    mut state: ResMut<EditorState>,
+    mut focus: ResMut<Focus>,
+) {
+    commands.remove_resource::<PlayTest>();
+    // The editor is laid out with the mouse, and the keys are needed to edit.
+    focus.entity = None;
+    focus.locked = true;
+    let name = match editor_level {
+        Some(level) => level.0.name.clone(),
+        None => {
+            let level = editor::new_level();
+            let name = level.name.clone();
+            commands.insert_resource(EditorLevel(level));
+            name
+        }
+    };
+    state.drag_offset = None;
+    state.drag_start = None;
+    state.snapped_to = None;
+    state.status.clear();
+
+    commands
+        .ui_root_with_alignment(JustifyContent::End, AlignItems::Center)
+        .insert(StateScoped(Screen::Editor))
+        .with_children(|children| {
+            children
+                .text_input(name, MAX_NAME_LENGTH)
+                .insert(NameInput)
+                .observe(rename_level);
+            children.label("").insert(StatusText);
+            children.label(help(&action_map));
+        });
+}
+
+fn exit_editor(mut focus: ResMut<Focus>) {
+    focus.locked = false;
+}
+
+fn rename_level(
+    trigger: Trigger<TextChanged>,
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+) {
+    let name = trigger.event().0.clone();
+    history.0.apply(&mut level.0, Edit::Rename(name));
+}
+
+/// Show the name of the level when it's changed some other way than typing it.
+fn show_level_name(
+    level: Res<EditorLevel>,
+    mut input_query: Query<&mut TextInput, With<NameInput>>,
+) {
+    if !level.is_changed() {
+        return;
+    }
+    for mut input in &mut input_query {
+        if input.text != level.0.name {
+            input.text.clone_from(&level.0.name);
+        }
+    }
+}
+
+/// Where the cursor points in the world, if it's in the window.
+fn cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+fn pick_and_drag(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    interaction_query: Query<&Interaction>,
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    mut state: ResMut<EditorState>,
+) {
+    if !mouse.pressed(MouseButton::Left) {
+        if state.drag_offset.is_some() {
+            state.drag_offset = None;
+            state.snapped_to = None;
+            if let (Some(index), Some(start)) = (state.selected, state.drag_start.take()) {
+                // Put the gear back and move it in one go, so the drag is undone as a whole.
+                let position = mem::replace(&mut level.0.gears[index].position, start);
+                history
+                    .0
+                    .apply(&mut level.0, Edit::MoveGear { index, position });
+            }
+        }
+        return;
+    }
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+
+    let (index, offset) = if mouse.just_pressed(MouseButton::Left) {
+        // Clicks on the UI are meant for it.
+        if interaction_query
+            .iter()
+            .any(|interaction| *interaction != Interaction::None)
+        {
+            return;
+        }
+        // Picking a gear leaves it where it is until it's dragged.
+        if let Some(index) = editor::gear_at(&level.0, cursor) {
+            state.selected = Some(index);
+            state.drag_offset = Some(level.0.gears[index].position - cursor);
+            state.drag_start = Some(level.0.gears[index].position);
+            return;
+        }
+        history.0.apply(&mut level.0, Edit::AddGear(cursor));
+        let index = level.0.gears.len() - 1;
+        state.selected = Some(index);
+        state.drag_offset = Some(Vec2::ZERO);
+        (index, Vec2::ZERO)
+    } else if let (Some(index), Some(offset)) = (state.selected, state.drag_offset) {
+        (index, offset)
+    } else {
+        return;
+    };
+
+    let mut position = cursor + offset;
+    state.snapped_to = None;
+    if !keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        let snap_angle = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        if let Some((neighbour, snapped)) =
+            editor::snap_position(&level.0, index, position, snap_angle)
+        {
+            state.snapped_to = Some(neighbour);
+            position = snapped;
+        }
+    }
+    // Only touch the level when the gear actually moves, so it isn't redrawn needlessly.
+    if level.0.gears[index].position != position {
+        level.0.gears[index].position = position;
+    }
+    // A new gear's history starts where it was snapped to.
+    if state.drag_start.is_none() {
+        state.drag_start = Some(level.0.gears[index].position);
+    }
+}
+
+fn resize_with_scroll(
+    mut wheel_events: EventReader<MouseWheel>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    state: Res<EditorState>,
+) {
+    let change: i32 = wheel_events
+        .read()
+        .map(|event| event.y.signum() as i32)
+        .sum();
+    if change == 0 {
+        return;
+    }
+    let hovered = cursor_position(&window_query, &camera_query)
+        .and_then(|cursor| editor::gear_at(&level.0, cursor));
+    if let Some(index) = hovered.or(state.selected) {
+        history
+            .0
+            .apply(&mut level.0, Edit::ResizeGear { index, change });
+    }
+}
+
+fn edit_selected_gear(
+    actions: Res<ActionState>,
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    mut state: ResMut<EditorState>,
+) {
+    let Some(index) = state.selected else {
+        return;
+    };
+    for (action, edit) in GEAR_EDITS {
+        if actions.just_pressed(action) {
+            history.0.apply(&mut level.0, edit(index));
+        }
+    }
+    if actions.just_pressed(InputAction::RemoveGear)
+        && history.0.apply(&mut level.0, Edit::RemoveGear(index))
+    {
+        state.selected = None;
+        state.drag_offset = None;
+        state.drag_start = None;
+    }
+}
+
+fn undo(
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    mut state: ResMut<EditorState>,
+) {
+    if state.drag_offset.is_some() {
+        return;
+    }
+    if !history.0.undo(&mut level.0) {
+        state.status = "Nothing to undo".to_string();
+    }
+    keep_valid_selection(&level.0, &mut state);
+}
+
+fn redo(
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    mut state: ResMut<EditorState>,
+) {
+    if state.drag_offset.is_some() {
+        return;
+    }
+    if !history.0.redo(&mut level.0) {
+        state.status = "Nothing to redo".to_string();
+    }
+    keep_valid_selection(&level.0, &mut state);
+}
+
+/// Deselect the selected gear if it isn't there anymore.
+fn keep_valid_selection(level: &Level, state: &mut EditorState) {
+    if state
+        .selected
+        .is_some_and(|index| index >= level.gears.len())
+    {
+        state.selected = None;
+    }
+}
+
+fn play_test(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut levels: ResMut<Assets<Level>>,
+    level: Res<EditorLevel>,
+    mut state: ResMut<EditorState>,
+) {
+    if let Err(error) = level.0.validate() {
+        state.status = format!("Cannot play-test: {error}");
+        return;
+    }
+    commands.insert_resource(SelectedLevel(levels.add(level.0.clone())));
+    commands.insert_resource(PlayTest);
+    next_screen.set(Screen::Playing);
+}
+
+fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
+    next_screen.set(Screen::Title);
+}
+
+/// Whether `action` was just pressed while holding Ctrl.
+fn shortcut_pressed(
+    action: InputAction,
+) -> impl Fn(Res<ButtonInput<KeyCode>>, Res<ActionState>) -> bool {
+    move |keys, actions| {
+        keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && actions.just_pressed(action)
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn save_level(level: Res<EditorLevel>, mut state: ResMut<EditorState>) {
+    let Some(data_dir) = storage::data_dir() else {
+        state.status = "Cannot save: there is no data directory on this platform".to_string();
+        return;
+    };
+    state.status = match editor::save(&level.0, data_dir.join(LEVELS_DIRECTORY)) {
+        Ok(path) => format!("Saved to {}", path.display()),
+        Err(error) => format!("Cannot save: {error}"),
+    };
+}
+
+/// Edit a level file that was dropped onto the window.
+#[cfg(not(target_family = "wasm"))]
+fn load_dropped_level(
+    mut drop_events: EventReader<FileDragAndDrop>,
+    mut level: ResMut<EditorLevel>,
+    mut history: ResMut<EditorHistory>,
+    mut state: ResMut<EditorState>,
+) {
+    for event in drop_events.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            match editor::load(path_buf) {
+                Ok(loaded) => {
+                    level.0 = loaded;
+                    history.0.clear();
+                    state.selected = None;
+                    state.status = format!("Editing {}", path_buf.display());
+                }
+                Err(error) => state.status = format!("Cannot edit {}: {error}", path_buf.display()),
+            }
+        }
+    }
+}
+
+/// Redraw the gears whenever the level changes. Each gear keeps its sprite
+/// unless gears are added or removed.
+fn sync_editor_gears(
+    mut commands: Commands,
+    level: Res<EditorLevel>,
+    image_handles: Res<HandleMap<ImageKey>>,
+    mut gear_query: Query<(Entity, &mut EditorGear, &mut Transform, &mut Sprite)>,
+) {
+    if !level.is_changed() {
+        return;
+    }
+    let gears = &level.0.gears;
+    let drives = gear_train::propagate(&level.0.train());
+    let movement = |index: usize| {
+        let gear = &gears[index];
+        RotationalMovement::new(drives[index].direction(), gear.teeth, gear.initial_step)
+    };
+    let color = |index: usize| {
+        let color = Color::from(gears[index].color);
+        if drives[index] == GearDrive::Jammed {
+            color.mix(&JAMMED_TINT, 0.6)
+        } else {
+            color
+        }
+    };
+    let scale =
+        |index: usize| Vec2::splat(gear_radius(gears[index].teeth) / BASE_GEAR_RADIUS).extend(1.0);
+
+    if gear_query.iter().len() == gears.len() {
+        for (_, mut editor_gear, mut transform, mut sprite) in &mut gear_query {
+            let index = editor_gear.index;
+            editor_gear.movement = movement(index);
+            transform.translation = gears[index].position.extend(1.0);
+            transform.scale = scale(index);
+            sprite.color = color(index);
+        }
+        return;
+    }
+
+    for (entity, ..) in &gear_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (index, gear) in gears.iter().enumerate() {
+        commands.spawn((
+            Name::new("Editor Gear"),
+            EditorGear {
+                index,
+                movement: movement(index),
+            },
+            SpriteBundle {
+                texture: image_handles[&ImageKey::Gear].clone_weak(),
+                transform: Transform::from_translation(gear.position.extend(1.0))
+                    .with_scale(scale(index)),
+                sprite: Sprite {
+                    color: color(index),
+                    ..default()
+                },
+                ..default()
+            },
+            StateScoped(Screen::Editor),
+        ));
+    }
+}
+
+fn spin_editor_gears(time: Res<Time>, mut gear_query: Query<(&EditorGear, &mut Transform)>) {
+    for (gear, mut transform) in &mut gear_query {
+        let rotation = gear.movement.get_initial_rotation()
+            + gear.movement.angular_velocity() * time.elapsed_seconds();
+        transform.rotation = Quat::from_rotation_z(rotation.rem_euclid(TAU));
+    }
+}
+
+fn draw_markers(mut gizmos: Gizmos, level: Res<EditorLevel>, state: Res<EditorState>) {
+    let level = &level.0;
+    let mut ring = |index: usize, margin: f32, color: Color| {
+        let gear = &level.gears[index];
+        gizmos.circle_2d(gear.position, gear_radius(gear.teeth) + margin, color);
+    };
+    ring(level.player.gear, 4.0, START_COLOR);
+    ring(level.goal, 10.0, GOAL_COLOR);
+    if let Some(index) = state.selected {
+        ring(index, 16.0, SELECTION_COLOR);
+    }
+
+    let mut link = |a: usize, b: usize, color: Color| {
+        gizmos.line_2d(level.gears[a].position, level.gears[b].position, color);
+    };
+    if let (Some(index), Some(neighbour)) = (state.selected, state.snapped_to) {
+        link(index, neighbour, SNAP_COLOR);
+    }
+    for warning in editor::placement_warnings(level) {
+        match warning {
+            PlacementWarning::Overlapping(a, b) => link(a, b, OVERLAP_COLOR),
+            PlacementWarning::TooFar(a, b) => link(a, b, TOO_FAR_COLOR),
+        }
+    }
+}
+
+/// Find the par of the level after every edit, without holding up the editor.
+/// Dragging a gear is an edit once it's dropped. A new edit replaces the
+/// search for the previous one.
+fn solve_level(level: Res<EditorLevel>, mut task: ResMut<SolverTask>) {
+    let problem = Problem::from_level(&level.0);
+    task.0 = Some(AsyncComputeTaskPool::get().spawn(async move {
+        match (
+            solver::solve(&problem, Objective::FewestJumps),
+            solver::solve(&problem, Objective::EarliestCompletion),
+        ) {
+            (Some(fewest), Some(earliest)) => format!(
+                "Par: {} jumps, {:.1}s",
+                fewest.jumps.len(),
+                earliest.completion_time()
+            ),
+            _ => "Warning: the goal cannot be reached".to_string(),
+        }
+    }));
+}
+
+fn show_par(mut task: ResMut<SolverTask>, mut state: ResMut<EditorState>) {
+    let Some(solving) = &mut task.0 else {
+        return;
+    };
+    if let Some(par) = block_on(future::poll_once(solving)) {
+        state.par = par;
+        task.0 = None;
+    }
+}
+
+fn show_status(
+    level: Res<EditorLevel>,
+    state: Res<EditorState>,
+    status_query: Query<&Children, With<StatusText>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !level.is_changed() && !state.is_changed() {
+        return;
+    }
+    let mut lines = Vec::new();
+    if !state.status.is_empty() {
+        lines.push(state.status.clone());
+    }
+    if !state.par.is_empty() {
+        lines.push(state.par.clone());
+    }
+    lines.extend(
+        editor::placement_warnings(&level.0)
+            .iter()
+            .map(|warning| format!("Warning: {warning}")),
+    );
+    let status = lines.join("\n");
+    for children in &status_query {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value.clone_from(&status);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use bevy::prelude::*;
 
 use super::Screen;
 use crate::{
-    game::assets::{HandleMap, ImageKey, SfxKey, SoundtrackKey},
+    game::assets::{CampaignKey, HandleMap, ImageKey, LevelKey, SfxKey, SoundtrackKey},
     ui::prelude::*,
 };
 
@@ -31,10 +31,15 @@ fn all_assets_loaded(
     image_handles: Res<HandleMap<ImageKey>>,
     sfx_handles: Res<HandleMap<SfxKey>>,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
+    level_handles: Res<HandleMap<LevelKey>>,
+    campaign_handles: Res<HandleMap<CampaignKey>>,
 ) -> bool {
     image_handles.all_loaded(&asset_server)
         && sfx_handles.all_loaded(&asset_server)
         && soundtrack_handles.all_loaded(&asset_server)
+        // A broken level file shouldn't lock the game on the loading screen.
+        && level_handles.all_finished(&asset_server)
+        && campaign_handles.all_finished(&asset_server)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
@@ -1,8 +1,11 @@
 //! The game's main screen states and transitions between them.
 
+mod controls;
 mod credits;
+pub mod editor;
 mod loading;
-mod playing;
+pub mod playing;
+mod settings;
 mod splash;
 mod title;
 
@@ -18,6 +21,9 @@ pub(super) fn plugin(app: &mut App) {
         title::plugin,
         credits::plugin,
         playing::plugin,
+        editor::plugin,
+        settings::plugin,
+        controls::plugin,
     ));
 }
 
@@ -30,6 +36,9 @@ pub enum Screen {
     Title,
     Credits,
     Playing,
+    Editor,
+    Settings,
+    Controls,
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
@@ -1,11 +1,26 @@
 //! The screen state for the main game loop.
 
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
 
-use super::Screen;
-use crate::ui::prelude::*;
+use super::{editor::PlayTest, Screen};
+use crate::ui::{focus::Focus, prelude::*};
 use crate::{
-    game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack, spawn::level::SpawnLevel},
+    game::{
+        actions::{action_just_pressed, ActionMap, InputAction},
+        assets::{HandleMap, LevelKey, SoundtrackKey},
+        audio::soundtrack::PlaySoundtrack,
+        gear_jump::LevelCompleted,
+        ghost::Ghost,
+        level::Level,
+        rotational_movement::AngularVelocity,
+        save::SaveData,
+        simulation::ActionQueue,
+        spawn::{
+            gear::Gear,
+            level::{CurrentLevel, SpawnLevel},
+            player::Player,
+        },
+    },
     screen::GameButtonAction,
 };
 
@@ -13,16 +28,49 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Playing), enter_playing);
     app.add_systems(OnExit(Screen::Playing), exit_playing);
 
+    app.observe(show_level_completed);
+
     app.register_type::<GameButtonAction>();
     app.add_systems(
         Update,
-        return_to_title_screen
-            .run_if(in_state(Screen::Playing).and_then(input_just_pressed(KeyCode::Escape))),
+        (
+            leave_level.run_if(action_just_pressed(InputAction::MenuBack)),
+            toggle_pause.run_if(action_just_pressed(InputAction::Pause)),
+            restart_level.run_if(action_just_pressed(InputAction::Restart)),
+        )
+            .run_if(in_state(Screen::Playing)),
     );
 }
 
-fn enter_playing(mut commands: Commands) {
-    commands.trigger(SpawnLevel);
+/// A message shown over the level, which goes away when it's restarted.
+#[derive(Component, Debug)]
+struct LevelMessage;
+
+#[derive(Component, Debug)]
+struct PauseMessage;
+
+/// The level to play instead of the default one.
+#[derive(Resource, Debug)]
+pub struct SelectedLevel(pub Handle<Level>);
+
+fn enter_playing(
+    mut commands: Commands,
+    level_handles: Res<HandleMap<LevelKey>>,
+    selected_level: Option<Res<SelectedLevel>>,
+    save: Res<SaveData>,
+    mut speed: ResMut<AngularVelocity>,
+    mut focus: ResMut<Focus>,
+) {
+    // There's no menu to navigate, and the keys are needed to play.
+    focus.entity = None;
+    focus.locked = true;
+
+    let level = match selected_level {
+        Some(selected_level) => selected_level.0.clone(),
+        None => level_handles[&LevelKey::ThreeGears].clone_weak(),
+    };
+    speed.0 = save.settings.speed;
+    commands.trigger(SpawnLevel(level));
     commands.trigger(PlaySoundtrack::Key(SoundtrackKey::Gameplay));
 
     commands
@@ -35,15 +83,106 @@ fn enter_playing(mut commands: Commands) {
             children
                 .button(">>")
                 .insert(GameButtonAction::IncreaseSpeed);
-            children.button("Jump (SPC)").insert(GameButtonAction::Jump);
+            children.button("Jump").insert(GameButtonAction::Jump);
+        });
+}
+
+/// The player can keep landing on the goal after completing the level, but
+/// the message is only shown once per run.
+fn show_level_completed(
+    _trigger: Trigger<LevelCompleted>,
+    mut commands: Commands,
+    action_map: Res<ActionMap>,
+    message_query: Query<(), With<LevelMessage>>,
+) {
+    if !message_query.is_empty() {
+        return;
+    }
+    commands
+        .ui_root()
+        .insert((StateScoped(Screen::Playing), LevelMessage))
+        .with_children(|children| {
+            children.header("Level complete!");
+            children.label(format!(
+                "Press {} to continue, {} to play again",
+                action_map.describe(InputAction::MenuBack),
+                action_map.describe(InputAction::Restart),
+            ));
         });
 }
 
-fn exit_playing(mut commands: Commands) {
+fn exit_playing(mut commands: Commands, mut time: ResMut<Time<Virtual>>, mut focus: ResMut<Focus>) {
     // We could use [`StateScoped`] on the sound playing entites instead.
     commands.trigger(PlaySoundtrack::Disable);
+    time.unpause();
+    focus.locked = false;
+}
+
+/// Pausing stops the simulation, which runs on virtual time. Input recorded
+/// for it is dropped, so it isn't all applied at once when resuming.
+fn toggle_pause(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    mut queue: ResMut<ActionQueue>,
+    action_map: Res<ActionMap>,
+    message_query: Query<Entity, With<PauseMessage>>,
+) {
+    queue.0.clear();
+    if time.is_paused() {
+        time.unpause();
+        for entity in &message_query {
+            commands.entity(entity).despawn_recursive();
+        }
+    } else {
+        time.pause();
+        commands
+            .ui_root()
+            .insert((StateScoped(Screen::Playing), PauseMessage))
+            .with_children(|children| {
+                children.header("Paused");
+                children.label(format!(
+                    "Press {} to resume",
+                    action_map.describe(InputAction::Pause)
+                ));
+            });
+    }
+}
+
+/// Start the current level over, with the starting speed from the settings.
+/// Restarting while paused resumes the game.
+fn restart_level(
+    mut commands: Commands,
+    current_level: Option<Res<CurrentLevel>>,
+    save: Res<SaveData>,
+    mut speed: ResMut<AngularVelocity>,
+    mut time: ResMut<Time<Virtual>>,
+    level_query: Query<
+        Entity,
+        Or<(
+            With<Gear>,
+            With<Player>,
+            With<Ghost>,
+            With<LevelMessage>,
+            With<PauseMessage>,
+        )>,
+    >,
+) {
+    let Some(current_level) = current_level else {
+        return;
+    };
+    for entity in &level_query {
+        commands.entity(entity).despawn_recursive();
+    }
+    time.unpause();
+    speed.0 = save.settings.speed;
+    commands.trigger(SpawnLevel(current_level.0.clone()));
 }
 
-fn return_to_title_screen(mut next_screen: ResMut<NextState<Screen>>) {
-    next_screen.set(Screen::Title);
+/// Go back to where the level was started from.
+fn leave_level(mut next_screen: ResMut<NextState<Screen>>, play_test: Option<Res<PlayTest>>) {
+    next_screen.set(if play_test.is_some() {
+        Screen::Editor
+    } else {
+        Screen::Title
+    });
 }
@@ -0,0 +1,126 @@
+//! A settings screen that can be accessed from the title screen. Changes are
+//! applied and saved right away.
+
+use bevy::{prelude::*, ui::Val::*};
+
+use super::Screen;
+use crate::{
+    game::{save::SaveData, settings},
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Settings), enter_settings);
+
+    app.register_type::<SettingsAction>();
+    app.add_systems(
+        Update,
+        handle_settings_action.run_if(in_state(Screen::Settings)),
+    );
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum SettingsAction {
+    Controls,
+    Back,
+}
+
+fn enter_settings(mut commands: Commands, save: Res<SaveData>) {
+    let current = &save.settings;
+    let speeds = settings::speeds();
+    let speed = speeds
+        .iter()
+        .position(|speed| *speed == current.speed)
+        .unwrap_or_default();
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Settings))
+        .with_children(|children| {
+            children.header("Settings");
+            setting(children, "Music volume", |children| {
+                children
+                    .slider(current.music_volume, 0.0..=1.0, settings::VOLUME_STEP)
+                    .observe(
+                        |trigger: Trigger<SliderChanged>, mut save: ResMut<SaveData>| {
+                            save.settings.music_volume = trigger.event().0;
+                        },
+                    );
+            });
+            setting(children, "Sound effects volume", |children| {
+                children
+                    .slider(current.sfx_volume, 0.0..=1.0, settings::VOLUME_STEP)
+                    .observe(
+                        |trigger: Trigger<SliderChanged>, mut save: ResMut<SaveData>| {
+                            save.settings.sfx_volume = trigger.event().0;
+                        },
+                    );
+            });
+            setting(children, "Starting speed", |children| {
+                let options = speeds.iter().map(|speed| format!("{speed}x"));
+                children.selector(options, speed).observe(
+                    move |trigger: Trigger<SelectionChanged>, mut save: ResMut<SaveData>| {
+                        save.settings.speed = speeds[trigger.event().0];
+                    },
+                );
+            });
+            setting(children, "Fullscreen", |children| {
+                children.toggle(current.fullscreen).observe(
+                    |trigger: Trigger<ToggleChanged>, mut save: ResMut<SaveData>| {
+                        save.settings.fullscreen = trigger.event().0;
+                    },
+                );
+            });
+            setting(children, "VSync", |children| {
+                children.toggle(current.vsync).observe(
+                    |trigger: Trigger<ToggleChanged>, mut save: ResMut<SaveData>| {
+                        save.settings.vsync = trigger.event().0;
+                    },
+                );
+            });
+            setting(children, "Reduced motion", |children| {
+                children.toggle(current.reduced_motion).observe(
+                    |trigger: Trigger<ToggleChanged>, mut save: ResMut<SaveData>| {
+                        save.settings.reduced_motion = trigger.event().0;
+                    },
+                );
+            });
+            children.button("Controls").insert(SettingsAction::Controls);
+            children.button("Back").insert(SettingsAction::Back);
+        });
+}
+
+/// Spawn a row with the name of a setting and the control that changes it.
+fn setting(children: &mut ChildBuilder, name: &str, control: impl FnOnce(&mut ChildBuilder)) {
+    children
+        .spawn((
+            Name::new("Setting"),
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|children| {
+            children.label(name);
+            control(children);
+        });
+}
+
+fn handle_settings_action(
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut button_query: InteractionQuery<&SettingsAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                SettingsAction::Controls => next_screen.set(Screen::Controls),
+                SettingsAction::Back => next_screen.set(Screen::Title),
+            }
+        }
+    }
+}
@@ -2,32 +2,74 @@
 
 use bevy::prelude::*;
 
-use super::Screen;
-use crate::ui::prelude::*;
+use super::{playing::SelectedLevel, Screen};
+use crate::{
+    game::{
+        assets::{CampaignKey, HandleMap},
+        campaign::{Campaign, CampaignProgress},
+        level::Level,
+        replay::{Playback, Replay, ReplayError, LAST_REPLAY_KEY},
+        save::{SaveData, SaveStorage},
+    },
+    ui::prelude::*,
+};
 
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(Screen::Title), enter_title);
 
     app.register_type::<TitleAction>();
     app.add_systems(Update, handle_title_action.run_if(in_state(Screen::Title)));
+    #[cfg(not(target_family = "wasm"))]
+    app.add_systems(Update, watch_dropped_replay.run_if(in_state(Screen::Title)));
 }
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 enum TitleAction {
+    /// Play the next level of the campaign.
     Play,
+    Editor,
+    /// Watch the last completed or saved run.
+    Replay,
+    Settings,
     Credits,
     /// Exit doesn't work well with embedded applications.
     #[cfg(not(target_family = "wasm"))]
     Exit,
 }
 
-fn enter_title(mut commands: Commands) {
+/// The campaign's next level, if the campaign loaded.
+fn next_campaign_level(
+    campaign_handles: &HandleMap<CampaignKey>,
+    campaigns: &Assets<Campaign>,
+    progress: &CampaignProgress,
+) -> Option<(String, Handle<Level>)> {
+    let (world, level) = campaigns
+        .get(&campaign_handles[&CampaignKey::Main])?
+        .next_level(progress)?;
+    Some((world.name.clone(), level.handle.clone()))
+}
+
+fn enter_title(
+    mut commands: Commands,
+    campaign_handles: Res<HandleMap<CampaignKey>>,
+    campaigns: Res<Assets<Campaign>>,
+    save: Res<SaveData>,
+    levels: Res<Assets<Level>>,
+) {
+    let next_level = next_campaign_level(&campaign_handles, &campaigns, &save.progress)
+        .and_then(|(world, handle)| Some(format!("{world}: {}", levels.get(&handle)?.name)));
     commands
         .ui_root()
         .insert(StateScoped(Screen::Title))
         .with_children(|children| {
             children.button("Play").insert(TitleAction::Play);
+            if let Some(next_level) = next_level {
+                children.label(next_level);
+            }
+            children.button("Editor").insert(TitleAction::Editor);
+            children.button("Replay").insert(TitleAction::Replay);
+            children.button("Settings").insert(TitleAction::Settings);
             children.button("Credits").insert(TitleAction::Credits);
 
             #[cfg(not(target_family = "wasm"))]
@@ -36,14 +78,36 @@ fn enter_title(mut commands: Commands) {
 }
 
 fn handle_title_action(
+    mut commands: Commands,
     mut next_screen: ResMut<NextState<Screen>>,
+    mut levels: ResMut<Assets<Level>>,
+    campaign_handles: Res<HandleMap<CampaignKey>>,
+    campaigns: Res<Assets<Campaign>>,
+    save: Res<SaveData>,
+    storage: Res<SaveStorage>,
     mut button_query: InteractionQuery<&TitleAction>,
     #[cfg(not(target_family = "wasm"))] mut app_exit: EventWriter<AppExit>,
 ) {
     for (interaction, action) in &mut button_query {
         if matches!(interaction, Interaction::Pressed) {
             match action {
-                TitleAction::Play => next_screen.set(Screen::Playing),
+                TitleAction::Play => {
+                    match next_campaign_level(&campaign_handles, &campaigns, &save.progress) {
+                        Some((_, handle)) => commands.insert_resource(SelectedLevel(handle)),
+                        // Fall back to the default level if the campaign failed to load.
+                        None => commands.remove_resource::<SelectedLevel>(),
+                    }
+                    next_screen.set(Screen::Playing);
+                }
+                TitleAction::Editor => next_screen.set(Screen::Editor),
+                TitleAction::Replay => watch_replay(
+                    &mut commands,
+                    &mut next_screen,
+                    &mut levels,
+                    Replay::read(storage.0.as_ref(), LAST_REPLAY_KEY)
+                        .and_then(|replay| replay.ok_or(ReplayError::Missing)),
+                ),
+                TitleAction::Settings => next_screen.set(Screen::Settings),
                 TitleAction::Credits => next_screen.set(Screen::Credits),
 
                 #[cfg(not(target_family = "wasm"))]
@@ -54,3 +118,41 @@ fn handle_title_action(
         }
     }
 }
+
+/// Play back a replay file that was dropped onto the window.
+#[cfg(not(target_family = "wasm"))]
+fn watch_dropped_replay(
+    mut commands: Commands,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut levels: ResMut<Assets<Level>>,
+    mut drop_events: EventReader<FileDragAndDrop>,
+) {
+    for event in drop_events.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            watch_replay(
+                &mut commands,
+                &mut next_screen,
+                &mut levels,
+                Replay::load(path_buf),
+            );
+        }
+    }
+}
+
+fn watch_replay(
+    commands: &mut Commands,
+    next_screen: &mut NextState<Screen>,
+    levels: &mut Assets<Level>,
+    replay: Result<Replay, ReplayError>,
+) {
+    let replay = match replay {
+        Ok(replay) => replay,
+        Err(error) => {
+            error!("Could not load replay: {error}");
+            return;
+        }
+    };
+    commands.insert_resource(SelectedLevel(levels.add(replay.level.clone())));
+    commands.insert_resource(Playback::new(replay));
+    next_screen.set(Screen::Playing);
+}
@@ -0,0 +1,337 @@
+//! Behavior of the interactive widgets spawned with [`Widgets`](super::widgets::Widgets).
+//! Every control triggers an event targeting its own entity when the player
+//! changes its value, so the code spawning it can [`observe`](EntityCommands::observe) it:
+//!
+//! ```ignore
+//! children
+//!     .slider(0.5, 0.0..=1.0, 0.1)
+//!     .observe(|trigger: Trigger<SliderChanged>| info!("{}", trigger.event().0));
+//! ```
+//!
+//! [`EntityCommands::observe`]: bevy::ecs::system::EntityCommands::observe
+
+use std::ops::RangeInclusive;
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    ui::Val::*,
+    window::PrimaryWindow,
+};
+
+use super::interaction::InteractionQuery;
+use crate::AppSet;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<(Slider, Toggle, Selector, TextInput)>();
+    app.add_systems(
+        Update,
+        (
+            (drag_sliders, show_slider_fill).chain(),
+            // Typing goes to the text input that was focused before this frame.
+            type_into_text_input,
+            (press_toggles, press_selectors, focus_text_inputs),
+            (
+                show_control_text::<Toggle>,
+                show_control_text::<Selector>,
+                show_control_text::<TextInput>,
+            ),
+        )
+            .chain()
+            .in_set(AppSet::RecordInput),
+    );
+}
+
+/// Triggered on a slider when it's dragged to a new value.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SliderChanged(pub f32);
+
+/// Triggered on a toggle or checkbox when it's switched.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ToggleChanged(pub bool);
+
+/// Triggered on a selector when it's moved on to the option at this index.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SelectionChanged(pub usize);
+
+/// Triggered on a text input whenever its text is edited.
+#[derive(Event, Debug, Clone)]
+pub struct TextChanged(pub String);
+
+/// A value between `min` and `max`, set by dragging.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Slider {
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    /// The value moves in steps of this size. Zero for no steps.
+    pub step: f32,
+}
+
+impl Slider {
+    pub fn new(value: f32, range: RangeInclusive<f32>, step: f32) -> Self {
+        Self {
+            value: value.clamp(*range.start(), *range.end()),
+            min: *range.start(),
+            max: *range.end(),
+            step,
+        }
+    }
+
+    /// How far along the slider the value is, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.0
+        }
+    }
+
+    /// The value at `fraction` along the slider, snapped to the step.
+    pub fn value_at(&self, fraction: f32) -> f32 {
+        let value = self.min + fraction.clamp(0.0, 1.0) * (self.max - self.min);
+        if self.step > 0.0 {
+            let steps = ((value - self.min) / self.step).round();
+            (self.min + steps * self.step).min(self.max)
+        } else {
+            value
+        }
+    }
+
+    /// The value `steps` steps away, snapped to the step. Sliders without
+    /// steps move a tenth of the way along instead.
+    pub fn value_after(&self, steps: f32) -> f32 {
+        let step = if self.step > 0.0 {
+            self.step / (self.max - self.min)
+        } else {
+            0.1
+        };
+        self.value_at(self.fraction() + steps * step)
+    }
+}
+
+/// The part of a [`Slider`] that fills up to its value.
+#[derive(Component, Debug)]
+pub struct SliderFill;
+
+/// A switch that's on or off, shown as one of two texts.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Toggle {
+    pub on: bool,
+    pub off_text: String,
+    pub on_text: String,
+}
+
+/// One of several options, moved on to the next one when pressed.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Selector {
+    pub options: Vec<String>,
+    pub selected: usize,
+}
+
+/// A single line of text that can be typed into after clicking it.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct TextInput {
+    pub text: String,
+    pub max_length: usize,
+    pub focused: bool,
+}
+
+/// Whether a text input has keyboard focus. Use this to ignore key presses
+/// that are meant for the text input.
+pub fn text_input_focused(input_query: Query<&TextInput>) -> bool {
+    input_query.iter().any(|input| input.focused)
+}
+
+/// Controls that show their value as text.
+pub(super) trait ControlText: Component {
+    fn text(&self) -> String;
+}
+
+impl ControlText for Toggle {
+    fn text(&self) -> String {
+        if self.on {
+            self.on_text.clone()
+        } else {
+            self.off_text.clone()
+        }
+    }
+}
+
+impl ControlText for Selector {
+    fn text(&self) -> String {
+        format!("< {} >", self.options[self.selected])
+    }
+}
+
+impl ControlText for TextInput {
+    fn text(&self) -> String {
+        if self.focused {
+            format!("{}|", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+fn show_control_text<C: ControlText>(
+    control_query: Query<(&C, &Children), Changed<C>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (control, children) in &control_query {
+        let mut texts = text_query.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            text.sections[0].value = control.text();
+        }
+    }
+}
+
+fn drag_sliders(
+    mut commands: Commands,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut slider_query: Query<(Entity, &Interaction, &Node, &GlobalTransform, &mut Slider)>,
+) {
+    let Some(cursor) = window_query
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+    for (entity, interaction, node, transform, mut slider) in &mut slider_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let left = transform.translation().x - node.size().x / 2.0;
+        let value = slider.value_at((cursor.x - left) / node.size().x);
+        if slider.value != value {
+            slider.value = value;
+            commands.trigger_targets(SliderChanged(value), entity);
+        }
+    }
+}
+
+fn show_slider_fill(
+    slider_query: Query<(&Slider, &Children), Changed<Slider>>,
+    mut fill_query: Query<&mut Style, With<SliderFill>>,
+) {
+    for (slider, children) in &slider_query {
+        let mut fills = fill_query.iter_many_mut(children);
+        while let Some(mut style) = fills.fetch_next() {
+            style.width = Percent(slider.fraction() * 100.0);
+        }
+    }
+}
+
+fn press_toggles(
+    mut commands: Commands,
+    mut toggle_query: InteractionQuery<(Entity, &mut Toggle)>,
+) {
+    for (interaction, (entity, mut toggle)) in &mut toggle_query {
+        if matches!(interaction, Interaction::Pressed) {
+            toggle.on = !toggle.on;
+            commands.trigger_targets(ToggleChanged(toggle.on), entity);
+        }
+    }
+}
+
+fn press_selectors(
+    mut commands: Commands,
+    mut selector_query: InteractionQuery<(Entity, &mut Selector)>,
+) {
+    for (interaction, (entity, mut selector)) in &mut selector_query {
+        if matches!(interaction, Interaction::Pressed) {
+            selector.selected = (selector.selected + 1) % selector.options.len();
+            commands.trigger_targets(SelectionChanged(selector.selected), entity);
+        }
+    }
+}
+
+/// Pressing a text input focuses it, clicking anywhere else takes the focus away.
+fn focus_text_inputs(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut input_query: Query<(Ref<Interaction>, &mut TextInput)>,
+) {
+    let clicked = mouse.just_pressed(MouseButton::Left);
+    for (interaction, mut input) in &mut input_query {
+        let pressed = *interaction == Interaction::Pressed;
+        if pressed && interaction.is_changed() && !input.focused {
+            input.focused = true;
+        } else if clicked && !pressed && input.focused {
+            input.focused = false;
+        }
+    }
+}
+
+/// Type into the focused text input. The keys it takes aren't seen as just
+/// pressed by anything else.
+fn type_into_text_input(
+    mut commands: Commands,
+    mut key_events: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut input_query: Query<(Entity, &mut TextInput)>,
+) {
+    let Some((entity, mut input)) = input_query.iter_mut().find(|(_, input)| input.focused) else {
+        key_events.clear();
+        return;
+    };
+    let mut changed = false;
+    for event in key_events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        keys.clear_just_pressed(event.key_code);
+        match &event.logical_key {
+            Key::Character(characters) => {
+                for character in characters.chars().filter(|c| !c.is_control()) {
+                    if input.text.chars().count() < input.max_length {
+                        input.text.push(character);
+                        changed = true;
+                    }
+                }
+            }
+            Key::Space if input.text.chars().count() < input.max_length => {
+                input.text.push(' ');
+                changed = true;
+            }
+            Key::Backspace => changed |= input.text.pop().is_some(),
+            Key::Enter | Key::Escape => input.focused = false,
+            _ => {}
+        }
+    }
+    if changed {
+        commands.trigger_targets(TextChanged(input.text.clone()), entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliders_snap_to_their_steps() {
+        let slider = Slider::new(2.0, 0.0..=1.0, 0.25);
+        assert_eq!(slider.value, 1.0);
+        assert_eq!(slider.fraction(), 1.0);
+        assert_eq!(slider.value_at(0.3), 0.25);
+        assert_eq!(slider.value_at(0.4), 0.5);
+        assert_eq!(slider.value_at(-1.0), 0.0);
+        assert_eq!(Slider::new(0.0, 10.0..=20.0, 0.0).value_at(0.5), 15.0);
+    }
+
+    #[test]
+    fn sliders_step_onto_their_steps() {
+        let slider = Slider::new(0.3, 0.0..=1.0, 0.25);
+        assert_eq!(slider.value_after(1.0), 0.5);
+        assert_eq!(slider.value_after(-1.0), 0.0);
+        assert_eq!(slider.value_after(-2.0), 0.0);
+        assert_eq!(Slider::new(15.0, 10.0..=20.0, 0.0).value_after(1.0), 16.0);
+    }
+}
@@ -0,0 +1,193 @@
+//! Keyboard and gamepad navigation. One widget at a time has the focus, which
+//! the menu actions move to the closest widget in their direction.
+//! [`InputAction::MenuConfirm`] presses the focused widget, like a click would.
+//! The actions used this way aren't seen as just pressed by anything else, so
+//! lock the [`Focus`] on screens that have no menu to navigate.
+
+use bevy::{prelude::*, ui::UiSystem};
+
+use super::controls::{text_input_focused, Slider, SliderChanged};
+use crate::game::actions::{update_action_state, ActionState, InputAction};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Focus>();
+    app.add_systems(
+        PreUpdate,
+        (
+            release_activated,
+            (forget_hidden_focus, navigate_focus, activate_focus)
+                .chain()
+                .run_if(not(text_input_focused).and_then(|focus: Res<Focus>| !focus.locked)),
+        )
+            .chain()
+            .after(UiSystem::Focus)
+            .after(update_action_state),
+    );
+}
+
+/// The widget that has the focus, if any.
+#[derive(Resource, Debug, Default)]
+pub struct Focus {
+    pub entity: Option<Entity>,
+    /// Set this while the keys that move the focus are needed for something else.
+    pub locked: bool,
+    /// The widget pressed by [`activate_focus`], to release it on the next frame.
+    activated: Option<Entity>,
+}
+
+/// Something that can be focused: any widget the mouse can interact with.
+type FocusableQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        &'static InheritedVisibility,
+    ),
+    With<Interaction>,
+>;
+
+/// The closest candidate in `direction` from `from`, favoring the ones that
+/// are lined up with it. Positions are in UI coordinates, with y pointing down.
+fn next_focus(
+    from: Vec2,
+    direction: Vec2,
+    candidates: impl IntoIterator<Item = (Entity, Vec2)>,
+) -> Option<Entity> {
+    candidates
+        .into_iter()
+        .filter_map(|(entity, position)| {
+            let offset = position - from;
+            let along = offset.dot(direction);
+            let across = offset.perp_dot(direction).abs();
+            (along > across).then_some((entity, along + 2.0 * across))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+/// Take the direction the player navigates in this frame.
+fn take_direction(actions: &mut ActionState) -> Option<Vec2> {
+    [
+        (InputAction::MenuUp, Vec2::NEG_Y),
+        (InputAction::MenuDown, Vec2::Y),
+        (InputAction::MenuLeft, Vec2::NEG_X),
+        (InputAction::MenuRight, Vec2::X),
+    ]
+    .into_iter()
+    .find(|(action, _)| actions.consume(*action))
+    .map(|(_, direction)| direction)
+}
+
+/// Keyboard presses don't get released by the mouse, so release them here.
+fn release_activated(mut focus: ResMut<Focus>, mut interaction_query: Query<&mut Interaction>) {
+    // Only touch the focus when there's something to release, as changing it plays a sound.
+    if focus.activated.is_none() {
+        return;
+    }
+    let Some(entity) = focus.bypass_change_detection().activated.take() else {
+        return;
+    };
+    if let Ok(mut interaction) = interaction_query.get_mut(entity) {
+        if *interaction == Interaction::Pressed {
+            *interaction = Interaction::None;
+        }
+    }
+}
+
+/// Drop the focus when its widget is gone, like when leaving a screen.
+fn forget_hidden_focus(mut focus: ResMut<Focus>, focusable_query: FocusableQuery) {
+    let Some(entity) = focus.entity else {
+        return;
+    };
+    if !focusable_query
+        .get(entity)
+        .is_ok_and(|(_, _, visibility)| visibility.get())
+    {
+        focus.entity = None;
+    }
+}
+
+fn navigate_focus(
+    mut commands: Commands,
+    mut actions: ResMut<ActionState>,
+    mut focus: ResMut<Focus>,
+    focusable_query: FocusableQuery,
+    mut slider_query: Query<&mut Slider>,
+) {
+    let Some(direction) = take_direction(&mut actions) else {
+        return;
+    };
+    let candidates = focusable_query
+        .iter()
+        .filter(|(_, _, visibility)| visibility.get())
+        .map(|(entity, transform, _)| (entity, transform.translation().truncate()));
+
+    let Some((entity, from)) = focus
+        .entity
+        .and_then(|entity| focusable_query.get(entity).ok())
+        .map(|(entity, transform, _)| (entity, transform.translation().truncate()))
+    else {
+        // Start from the top left widget.
+        focus.entity = candidates
+            .min_by(|(_, a), (_, b)| (a.y, a.x).partial_cmp(&(b.y, b.x)).unwrap())
+            .map(|(entity, _)| entity);
+        return;
+    };
+
+    // Left and right move a focused slider instead.
+    if direction.y == 0.0 {
+        if let Ok(mut slider) = slider_query.get_mut(entity) {
+            let value = slider.value_after(direction.x);
+            if slider.value != value {
+                slider.value = value;
+                commands.trigger_targets(SliderChanged(value), entity);
+            }
+            return;
+        }
+    }
+
+    if let Some(next) = next_focus(from, direction, candidates) {
+        focus.entity = Some(next);
+    }
+}
+
+/// Press the focused widget.
+fn activate_focus(
+    mut actions: ResMut<ActionState>,
+    mut focus: ResMut<Focus>,
+    mut interaction_query: Query<&mut Interaction>,
+) {
+    let Some(entity) = focus.entity else {
+        return;
+    };
+    let Ok(mut interaction) = interaction_query.get_mut(entity) else {
+        return;
+    };
+    if actions.consume(InputAction::MenuConfirm) {
+        *interaction = Interaction::Pressed;
+        focus.bypass_change_detection().activated = Some(entity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_moves_to_the_closest_widget_in_line() {
+        let [above, below, right, below_right] =
+            [0, 1, 2, 3].map(|index| Entity::from_raw(index + 1));
+        let candidates = [
+            (above, Vec2::new(100.0, 0.0)),
+            (below, Vec2::new(100.0, 200.0)),
+            (right, Vec2::new(300.0, 100.0)),
+            (below_right, Vec2::new(130.0, 170.0)),
+        ];
+        let from = Vec2::new(100.0, 100.0);
+        assert_eq!(next_focus(from, Vec2::NEG_Y, candidates), Some(above));
+        assert_eq!(next_focus(from, Vec2::Y, candidates), Some(below));
+        assert_eq!(next_focus(from, Vec2::X, candidates), Some(right));
+        assert_eq!(next_focus(from, Vec2::NEG_X, candidates), None);
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use super::focus::Focus;
 use crate::game::{assets::SfxKey, audio::sfx::PlaySfx};
 
 pub(super) fn plugin(app: &mut App) {
@@ -10,7 +11,7 @@ pub(super) fn plugin(app: &mut App) {
 pub type InteractionQuery<'w, 's, T> =
     Query<'w, 's, (&'static Interaction, T), Changed<Interaction>>;
 
-/// Palette for widget interactions.
+/// Palette for widget interactions. The focused widget looks hovered.
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
 pub struct InteractionPalette {
@@ -20,10 +21,21 @@ pub struct InteractionPalette {
 }
 
 fn apply_interaction_palette(
-    mut palette_query: InteractionQuery<(&InteractionPalette, &mut BackgroundColor)>,
+    focus: Res<Focus>,
+    mut palette_query: Query<(
+        Entity,
+        Ref<Interaction>,
+        &InteractionPalette,
+        &mut BackgroundColor,
+    )>,
 ) {
-    for (interaction, (palette, mut background)) in &mut palette_query {
-        *background = match interaction {
+    for (entity, interaction, palette, mut background) in &mut palette_query {
+        if !interaction.is_changed() && !focus.is_changed() {
+            continue;
+        }
+        let focused = focus.entity == Some(entity);
+        *background = match *interaction {
+            Interaction::None if focused => palette.hovered,
             Interaction::None => palette.none,
             Interaction::Hovered => palette.hovered,
             Interaction::Pressed => palette.pressed,
@@ -34,8 +46,16 @@ fn apply_interaction_palette(
 
 fn trigger_interaction_sfx(
     mut interactions: Query<&Interaction, Changed<Interaction>>,
+    focus: Res<Focus>,
+    mut last_focus: Local<Option<Entity>>,
     mut commands: Commands,
 ) {
+    if focus.entity != *last_focus {
+        *last_focus = focus.entity;
+        if focus.entity.is_some() {
+            commands.trigger(PlaySfx::Key(SfxKey::ButtonHover));
+        }
+    }
     for interaction in &mut interactions {
         match interaction {
             Interaction::Hovered => commands.trigger(PlaySfx::Key(SfxKey::ButtonHover)),
@@ -3,12 +3,17 @@
 // Unused utilities and re-exports may trigger these lints undesirably.
 #![allow(dead_code, unused_imports)]
 
+pub mod controls;
+pub mod focus;
 pub mod interaction;
 pub mod palette;
 mod widgets;
 
 pub mod prelude {
     pub use super::{
+        controls::{
+            text_input_focused, SelectionChanged, SliderChanged, TextChanged, ToggleChanged,
+        },
         interaction::{InteractionPalette, InteractionQuery},
         palette as ui_palette,
         widgets::{Containers as _, Widgets as _},
@@ -18,5 +23,5 @@ pub mod prelude {
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins(interaction::plugin);
+    app.add_plugins((interaction::plugin, controls::plugin, focus::plugin));
 }
@@ -7,4 +7,6 @@ pub const BUTTON_TEXT: Color = Color::srgb(0.925, 0.925, 0.925);
 pub const LABEL_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 pub const HEADER_TEXT: Color = Color::srgb(0.867, 0.827, 0.412);
 
+pub const SLIDER_FILL: Color = Color::srgb(0.867, 0.827, 0.412);
+
 pub const NODE_BACKGROUND: Color = Color::srgb(0.286, 0.478, 0.773);
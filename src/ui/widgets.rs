@@ -1,23 +1,55 @@
 //! Helper traits for creating common widgets.
 
+use std::ops::RangeInclusive;
+
 use bevy::{ecs::system::EntityCommands, prelude::*, ui::Val::*};
 
-use super::{interaction::InteractionPalette, palette::*};
+use super::{
+    controls::{ControlText, Selector, Slider, SliderFill, TextInput, Toggle},
+    interaction::InteractionPalette,
+    palette::*,
+};
 
 /// An extension trait for spawning UI widgets.
 pub trait Widgets {
     /// Spawn a simple button with text.
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
+
+    /// Spawn a button with smaller text than [`Widgets::button`], for crowded screens.
+    fn small_button(&mut self, text: impl Into<String>, width: f32) -> EntityCommands<'_>;
 
     /// Spawn a simple header label. Bigger than [`Widgets::label`].
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
 
     /// Spawn a simple text label.
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands;
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_>;
+
+    /// Spawn a slider that can be dragged between the ends of `range`, in
+    /// steps of `step`. Triggers [`SliderChanged`](super::controls::SliderChanged).
+    fn slider(&mut self, value: f32, range: RangeInclusive<f32>, step: f32) -> EntityCommands<'_>;
+
+    /// Spawn a button that switches between "On" and "Off".
+    /// Triggers [`ToggleChanged`](super::controls::ToggleChanged).
+    fn toggle(&mut self, on: bool) -> EntityCommands<'_>;
+
+    /// Spawn a labeled checkbox. Triggers [`ToggleChanged`](super::controls::ToggleChanged).
+    fn checkbox(&mut self, text: impl Into<String>, checked: bool) -> EntityCommands<'_>;
+
+    /// Spawn a button that cycles through `options`, starting at `selected`.
+    /// Triggers [`SelectionChanged`](super::controls::SelectionChanged).
+    fn selector(
+        &mut self,
+        options: impl IntoIterator<Item = impl Into<String>>,
+        selected: usize,
+    ) -> EntityCommands<'_>;
+
+    /// Spawn a single-line text input that takes up to `max_length` characters.
+    /// Triggers [`TextChanged`](super::controls::TextChanged).
+    fn text_input(&mut self, text: impl Into<String>, max_length: usize) -> EntityCommands<'_>;
 }
 
 impl<T: Spawn> Widgets for T {
-    fn button(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn button(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Button"),
             ButtonBundle {
@@ -53,7 +85,39 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
-    fn header(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn small_button(&mut self, text: impl Into<String>, width: f32) -> EntityCommands<'_> {
+        let mut entity = self.spawn((
+            Name::new("Small Button"),
+            ButtonBundle {
+                style: Style {
+                    width: Px(width),
+                    height: Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            control_palette(),
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Small Button Text"),
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 20.0,
+                        color: BUTTON_TEXT,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+        entity
+    }
+
+    fn header(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Header"),
             NodeBundle {
@@ -84,7 +148,7 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
-    fn label(&mut self, text: impl Into<String>) -> EntityCommands {
+    fn label(&mut self, text: impl Into<String>) -> EntityCommands<'_> {
         let mut entity = self.spawn((
             Name::new("Label"),
             NodeBundle {
@@ -112,13 +176,134 @@ impl<T: Spawn> Widgets for T {
         });
         entity
     }
+
+    fn slider(&mut self, value: f32, range: RangeInclusive<f32>, step: f32) -> EntityCommands<'_> {
+        let slider = Slider::new(value, range, step);
+        let fraction = slider.fraction();
+        let mut entity = self.spawn((
+            Name::new("Slider"),
+            ButtonBundle {
+                style: Style {
+                    width: Px(200.0),
+                    height: Px(30.0),
+                    ..default()
+                },
+                background_color: BackgroundColor(NODE_BACKGROUND),
+                ..default()
+            },
+            control_palette(),
+            slider,
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Slider Fill"),
+                NodeBundle {
+                    style: Style {
+                        width: Percent(fraction * 100.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(SLIDER_FILL),
+                    ..default()
+                },
+                SliderFill,
+            ));
+        });
+        entity
+    }
+
+    fn toggle(&mut self, on: bool) -> EntityCommands<'_> {
+        let toggle = Toggle {
+            on,
+            off_text: "Off".to_string(),
+            on_text: "On".to_string(),
+        };
+        control(self, "Toggle", Px(200.0), toggle)
+    }
+
+    fn checkbox(&mut self, text: impl Into<String>, checked: bool) -> EntityCommands<'_> {
+        let text = text.into();
+        let toggle = Toggle {
+            on: checked,
+            off_text: format!("[ ] {text}"),
+            on_text: format!("[X] {text}"),
+        };
+        control(self, "Checkbox", Px(300.0), toggle)
+    }
+
+    fn selector(
+        &mut self,
+        options: impl IntoIterator<Item = impl Into<String>>,
+        selected: usize,
+    ) -> EntityCommands<'_> {
+        let options: Vec<String> = options.into_iter().map(Into::into).collect();
+        assert!(selected < options.len(), "selected option doesn't exist");
+        control(self, "Selector", Px(200.0), Selector { options, selected })
+    }
+
+    fn text_input(&mut self, text: impl Into<String>, max_length: usize) -> EntityCommands<'_> {
+        let input = TextInput {
+            text: text.into(),
+            max_length,
+            focused: false,
+        };
+        control(self, "Text Input", Px(500.0), input)
+    }
+}
+
+fn control_palette() -> InteractionPalette {
+    InteractionPalette {
+        none: NODE_BACKGROUND,
+        hovered: BUTTON_HOVERED_BACKGROUND,
+        pressed: BUTTON_PRESSED_BACKGROUND,
+    }
+}
+
+/// Spawn a button showing the text of a control, which keeps it up to date.
+fn control<'a, C: ControlText>(
+    spawner: &'a mut impl Spawn,
+    name: &'static str,
+    width: Val,
+    control: C,
+) -> EntityCommands<'a> {
+    let text = control.text();
+    let mut entity = spawner.spawn((
+        Name::new(name),
+        ButtonBundle {
+            style: Style {
+                width,
+                height: Px(50.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(NODE_BACKGROUND),
+            ..default()
+        },
+        control_palette(),
+        control,
+    ));
+    entity.with_children(|children| {
+        children.spawn((
+            Name::new(format!("{name} Text")),
+            TextBundle::from_section(
+                text,
+                TextStyle {
+                    font_size: 24.0,
+                    color: BUTTON_TEXT,
+                    ..default()
+                },
+            ),
+        ));
+    });
+    entity
 }
 
 /// An extension trait for spawning UI containers.
 pub trait Containers {
     /// Spawns a root node that covers the full screen
     /// and centers its content horizontally and vertically.
-    fn ui_root(&mut self) -> EntityCommands;
+    fn ui_root(&mut self) -> EntityCommands<'_>;
 
     /// Spawns a root node that covers the full screen
     /// and centers its content as specified
@@ -126,11 +311,11 @@ pub trait Containers {
         &mut self,
         justify: JustifyContent,
         align: AlignItems,
-    ) -> EntityCommands;
+    ) -> EntityCommands<'_>;
 }
 
 impl Containers for Commands<'_, '_> {
-    fn ui_root(&mut self) -> EntityCommands {
+    fn ui_root(&mut self) -> EntityCommands<'_> {
         self.ui_root_with_alignment(JustifyContent::Center, AlignItems::Center)
     }
 
@@ -138,7 +323,7 @@ impl Containers for Commands<'_, '_> {
         &mut self,
         justify: JustifyContent,
         align: AlignItems,
-    ) -> EntityCommands {
+    ) -> EntityCommands<'_> {
         self.spawn((
             Name::new("UI Root"),
             NodeBundle {
@@ -163,17 +348,17 @@ impl Containers for Commands<'_, '_> {
 /// are able to spawn entities.
 /// Ideally, this trait should be [part of Bevy itself](https://github.com/bevyengine/bevy/issues/14231).
 trait Spawn {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands;
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_>;
 }
 
 impl Spawn for Commands<'_, '_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }
 
 impl Spawn for ChildBuilder<'_> {
-    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands {
+    fn spawn<B: Bundle>(&mut self, bundle: B) -> EntityCommands<'_> {
         self.spawn(bundle)
     }
 }
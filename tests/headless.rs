@@ -0,0 +1,130 @@
+use bevy::math::Vec2;
+use bevy_gear_slip::headless::{GameplayEvent, Harness, Level, PlayerAction};
+
+/// Long enough to go around any gear at the default speed.
+const MAX_STEPS: u64 = 2000;
+
+fn three_gears() -> Level {
+    ron::from_str(include_str!("../assets/levels/three_gears.level.ron")).unwrap()
+}
+
+/// Step `harness` until the player is well inside the capture zone of the
+/// gear at `center`, which has the default size.
+fn wait_until_near(harness: &mut Harness, center: Vec2) {
+    for _ in 0..MAX_STEPS {
+        let distance = harness.player().position.distance(center);
+        if (88.0..96.0).contains(&distance) {
+            return;
+        }
+        harness.step(&[]);
+    }
+    panic!("the player never came near the gear at {center}");
+}
+
+#[test]
+fn every_step_advances_the_tick() {
+    let mut harness = Harness::new(three_gears());
+    assert_eq!(harness.tick(), 0);
+    harness.run(10);
+    assert_eq!(harness.tick(), 10);
+}
+
+#[test]
+fn the_player_orbits_the_start_gear() {
+    let mut harness = Harness::new(three_gears());
+    let start = harness.gears()[0].position;
+    let before = harness.player();
+    harness.run(30);
+    let after = harness.player();
+
+    assert_eq!(after.anchor, start);
+    assert!(!after.flying);
+    assert!((after.position.distance(start) - 92.0).abs() < 0.01);
+    assert_ne!(after.position, before.position);
+}
+
+#[test]
+fn jumping_to_the_goal_completes_the_level() {
+    let mut harness = Harness::new(three_gears());
+    let gears = harness.gears();
+    let goal = gears.iter().position(|gear| gear.goal).unwrap();
+    wait_until_near(&mut harness, gears[goal].position);
+
+    harness.step(&[PlayerAction::Jump]);
+    assert_eq!(harness.player().anchor, gears[goal].position);
+    assert_eq!(
+        harness.events(),
+        [
+            GameplayEvent::Jumped { from: 0 },
+            GameplayEvent::Landed { to: goal },
+            GameplayEvent::LevelCompleted,
+        ]
+    );
+}
+
+#[test]
+fn missed_jumps_fly_off_and_respawn() {
+    let mut harness = Harness::new(three_gears());
+    let start = harness.gears()[0].position;
+    harness.step(&[PlayerAction::Jump]);
+    assert!(harness.player().flying);
+    assert_eq!(
+        harness.events(),
+        [GameplayEvent::Jumped { from: 0 }, GameplayEvent::Missed,]
+    );
+
+    for _ in 0..MAX_STEPS {
+        if !harness.player().flying {
+            break;
+        }
+        harness.step(&[]);
+    }
+    let player = harness.player();
+    assert!(!player.flying);
+    // Either the player flew into another gear or started over.
+    match harness.events()[2..] {
+        [GameplayEvent::Landed { to }] => assert_ne!(to, 0),
+        [GameplayEvent::Fell] => assert_eq!(player.anchor, start),
+        ref events => panic!("unexpected events {events:?}"),
+    }
+}
+
+#[test]
+fn speed_changes_stop_at_the_limits() {
+    let mut harness = Harness::new(three_gears());
+    for _ in 0..5 {
+        harness.step(&[PlayerAction::SpeedUp]);
+    }
+    harness.step(&[PlayerAction::SpeedDown]);
+    // Starting from 1, the speed can only double twice.
+    assert_eq!(
+        harness.events(),
+        [
+            GameplayEvent::SpeedChanged { from: 1.0, to: 2.0 },
+            GameplayEvent::SpeedChanged { from: 2.0, to: 4.0 },
+            GameplayEvent::SpeedChanged { from: 4.0, to: 2.0 },
+        ]
+    );
+}
+
+#[test]
+fn identical_inputs_give_identical_runs() {
+    let script = |harness: &mut Harness| {
+        harness.run(17);
+        harness.step(&[PlayerAction::SpeedUp]);
+        harness.run(40);
+        harness.step(&[PlayerAction::Jump]);
+        harness.run(100);
+        harness.step(&[PlayerAction::SpeedDown, PlayerAction::Jump]);
+        harness.run(300);
+    };
+
+    let mut first = Harness::new(three_gears());
+    let mut second = Harness::new(three_gears());
+    script(&mut first);
+    script(&mut second);
+
+    assert_eq!(first.player(), second.player());
+    assert_eq!(first.gears(), second.gears());
+    assert_eq!(first.events(), second.events());
+}